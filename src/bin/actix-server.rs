@@ -0,0 +1,610 @@
+use actix_cors::Cors;
+use actix_web::{delete, get, post, web, App, HttpRequest, HttpResponse, HttpServer};
+use actix_ws::{AggregatedMessage, AggregatedMessageStream, Session};
+use prototype::file_io::{get_settings, override_settings, Settings};
+use prototype::logging::Logger;
+use prototype::items::Dependency;
+use prototype::parse_service::{Batch, JobId, JobInfo, JobRegistry, JobState, ProgressInfo};
+use prototype::parsers::LogParser;
+use prototype::parsers::z3parser1::Z3Parser1;
+use prototype::query::{self, ListQuery};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::sync::mpsc::TryRecvError;
+use std::time::Duration;
+
+const ADDRESS: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 8080;
+/// Log parsed by `GET /sample`
+const SAMPLE_LOG: &str = "logs/heaps-simpler.log";
+/// Largest accepted log upload, in bytes
+const MAX_LOG_SIZE: usize = 1 << 30;
+/// How often `GET /stream` sends progress and new results
+const STREAM_INTERVAL: Duration = Duration::from_millis(200);
+
+/// What a parse request responds with.
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    /// the rendered graph
+    #[default]
+    Svg,
+    /// the rendered graph and all dependencies
+    Json,
+}
+
+#[derive(Deserialize)]
+struct ParseQuery {
+    /// name of the uploaded log, recorded as the source of the outputs
+    #[serde(default = "default_name")]
+    name: String,
+    #[serde(default)]
+    format: Format,
+}
+
+fn default_name() -> String {
+    String::from("upload")
+}
+
+/// Body of `POST /results`.
+#[derive(Deserialize)]
+struct NewJob {
+    #[serde(default = "default_name")]
+    name: String,
+    /// contents of the log
+    text: String,
+    /// settings that differ from the server's settings file
+    #[serde(default)]
+    settings: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Query parameters of the item endpoints.
+#[derive(Deserialize)]
+struct ItemsQuery {
+    #[serde(default)]
+    offset: usize,
+    /// page size; the default page size if left out
+    #[serde(default)]
+    limit: usize,
+    /// comma-separated names of the fields to return; all fields if left out
+    fields: Option<String>,
+    /// ID of the quantifier whose instantiations to list
+    quantifier: Option<String>,
+}
+
+impl ItemsQuery {
+    fn fields(&self) -> Vec<String> {
+        self.fields.iter().flat_map(|fields| fields.split(',')).filter(|field| !field.is_empty()).map(String::from).collect()
+    }
+
+    fn list_query(&self) -> ListQuery {
+        ListQuery { offset: self.offset, limit: self.limit, fields: self.fields(), quantifier: self.quantifier.clone() }
+    }
+}
+
+/// Event sent over the WebSocket of `GET /stream`, as JSON tagged with `event`.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+enum StreamEvent<'a> {
+    /// the job was queued
+    Started(&'a JobInfo),
+    Progress { progress: ProgressInfo },
+    /// results found since the previous batch
+    Batch(&'a Batch),
+    /// the job will not change any more; the socket is closed after this
+    Finished(&'a JobInfo),
+    /// the job could not be submitted; the socket is closed after this
+    Error { error: String },
+}
+
+/// Summary of a job and, once there are results, the rendered graph and all dependencies.
+#[derive(Serialize)]
+struct JobResponse<'a> {
+    #[serde(flatten)]
+    info: JobInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    svg: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dependencies: Option<&'a [Dependency]>,
+}
+
+/// Parses the log in the request body. Responds with the rendered graph, or with JSON if `format=json`.
+#[post("/parse")]
+async fn parse(registry: web::Data<JobRegistry>, settings: web::Data<Settings>, query: web::Query<ParseQuery>, body: web::Bytes) -> HttpResponse {
+    let Ok(log) = String::from_utf8(body.to_vec()) else {
+        return HttpResponse::BadRequest().body("The log is not valid UTF-8");
+    };
+    let id = registry.submit_text(&query.name, log, &settings);
+    respond(registry, id, query.format).await
+}
+
+/// Parses the sample log. Responds like `POST /parse`.
+#[get("/sample")]
+async fn sample(registry: web::Data<JobRegistry>, settings: web::Data<Settings>, query: web::Query<ParseQuery>) -> HttpResponse {
+    match registry.submit_file(SAMPLE_LOG, &settings) {
+        Ok(id) => respond(registry, id, query.format).await,
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+/// Waits for job `id` without blocking the server, and responds with its results in `format`.
+async fn respond(registry: web::Data<JobRegistry>, id: JobId, format: Format) -> HttpResponse {
+    let waiting = registry.clone();
+    let info = match web::block(move || waiting.wait(id)).await {
+        Ok(Some(info)) => info,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    if let JobState::Failed { error } = info.state {
+        return HttpResponse::InternalServerError().body(error);
+    }
+    let Some(output) = registry.output(id) else {
+        return HttpResponse::InternalServerError().body("The parse was cancelled");
+    };
+    match format {
+        Format::Svg => HttpResponse::Ok().content_type("image/svg+xml").body(output.svg.clone()),
+        Format::Json => HttpResponse::Ok().json(JobResponse {
+            info,
+            svg: Some(&output.svg),
+            dependencies: Some(output.parser.get_dependencies()),
+        }),
+    }
+}
+
+/// Queues a log for parsing. Responds with the job's summary, including its ID.
+#[post("/results")]
+async fn submit_job(registry: web::Data<JobRegistry>, settings: web::Data<Settings>, job: web::Json<NewJob>) -> HttpResponse {
+    let NewJob { name, text, settings: overrides } = job.into_inner();
+    let settings = match override_settings(&settings, overrides) {
+        Ok(settings) => settings,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let id = registry.submit_text(&name, text, &settings);
+    HttpResponse::Accepted().json(registry.get(id))
+}
+
+/// Summaries of all jobs.
+#[get("/results")]
+async fn list_jobs(registry: web::Data<JobRegistry>) -> HttpResponse {
+    HttpResponse::Ok().json(registry.list())
+}
+
+/// Progress of a job, with its results once there are any.
+#[get("/results/{id}")]
+async fn get_job(registry: web::Data<JobRegistry>, id: web::Path<JobId>) -> HttpResponse {
+    let Some(info) = registry.get(*id) else {
+        return HttpResponse::NotFound().finish();
+    };
+    let output = registry.output(*id);
+    HttpResponse::Ok().json(JobResponse {
+        info,
+        svg: output.as_ref().map(|output| output.svg.as_str()),
+        dependencies: output.as_ref().map(|output| output.parser.get_dependencies().as_slice()),
+    })
+}
+
+/// Stops a queued or running job.
+#[post("/results/{id}/cancel")]
+async fn cancel_job(registry: web::Data<JobRegistry>, id: web::Path<JobId>) -> HttpResponse {
+    match registry.cancel(*id) {
+        Ok(info) => HttpResponse::Ok().json(info),
+        Err(_) if registry.get(*id).is_none() => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::Conflict().body(e),
+    }
+}
+
+/// Drops a finished job and its results.
+#[delete("/results/{id}")]
+async fn delete_job(registry: web::Data<JobRegistry>, id: web::Path<JobId>) -> HttpResponse {
+    match registry.remove(*id) {
+        Ok(info) => HttpResponse::Ok().json(info),
+        Err(_) if registry.get(*id).is_none() => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::Conflict().body(e),
+    }
+}
+
+/// Parses a log while streaming its progress and results over a WebSocket.
+/// The first message must be a job as for `POST /results`; a `stop` message afterwards cancels it.
+/// The job keeps running if the socket is closed.
+#[get("/stream")]
+async fn stream_parse(req: HttpRequest, body: web::Payload, registry: web::Data<JobRegistry>, settings: web::Data<Settings>) -> actix_web::Result<HttpResponse> {
+    let (response, session, messages) = actix_ws::handle(&req, body)?;
+    let messages = messages.max_frame_size(MAX_LOG_SIZE).aggregate_continuations().max_continuation_size(MAX_LOG_SIZE);
+    actix_web::rt::spawn(stream_job(session, messages, registry, settings));
+    Ok(response)
+}
+
+/// Submits the job sent over `session`, then sends its progress and new results every `STREAM_INTERVAL` until it has finished.
+async fn stream_job(mut session: Session, mut messages: AggregatedMessageStream, registry: web::Data<JobRegistry>, settings: web::Data<Settings>) {
+    let job = loop {
+        match messages.recv().await {
+            Some(Ok(AggregatedMessage::Text(text))) => break text,
+            Some(Ok(AggregatedMessage::Ping(bytes))) => { let _ = session.pong(&bytes).await; },
+            Some(Ok(AggregatedMessage::Binary(_) | AggregatedMessage::Pong(_))) => {},
+            Some(Ok(AggregatedMessage::Close(_)) | Err(_)) | None => return,
+        }
+    };
+    let job = serde_json::from_str::<NewJob>(&job).map_err(|e| e.to_string())
+        .and_then(|job| Ok((override_settings(&settings, job.settings)?, job.name, job.text)));
+    let (settings, name, text) = match job {
+        Ok(job) => job,
+        Err(error) => {
+            let _ = send_event(&mut session, &StreamEvent::Error { error }).await;
+            let _ = session.close(None).await;
+            return;
+        }
+    };
+    let (id, batches) = registry.submit_text_streaming(&name, text, &settings);
+    let (stopper, mut ponger) = (registry.clone(), session.clone());
+    actix_web::rt::spawn(async move {
+        while let Some(Ok(message)) = messages.recv().await {
+            match message {
+                AggregatedMessage::Text(text) if text.trim() == "stop" => { let _ = stopper.cancel(id); },
+                AggregatedMessage::Ping(bytes) => { let _ = ponger.pong(&bytes).await; },
+                AggregatedMessage::Close(_) => break,
+                _ => {},
+            }
+        }
+    });
+    let Some(info) = registry.get(id) else { return };
+    if send_event(&mut session, &StreamEvent::Started(&info)).await.is_err() {
+        return;
+    }
+    let mut progress = info.progress;
+    let mut interval = actix_web::rt::time::interval(STREAM_INTERVAL);
+    loop {
+        interval.tick().await;
+        // the receiver is disconnected once the job has finished and all batches have been sent
+        let finished = loop {
+            match batches.try_recv() {
+                Ok(batch) => if send_event(&mut session, &StreamEvent::Batch(&batch)).await.is_err() { return },
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        let Some(info) = registry.get(id) else { return };
+        if info.progress != progress {
+            progress = info.progress;
+            if send_event(&mut session, &StreamEvent::Progress { progress }).await.is_err() {
+                return;
+            }
+        }
+        if finished {
+            let _ = send_event(&mut session, &StreamEvent::Finished(&info)).await;
+            let _ = session.close(None).await;
+            return;
+        }
+    }
+}
+
+/// Sends `event` over `session` as JSON.
+async fn send_event(session: &mut Session, event: &StreamEvent<'_>) -> Result<(), actix_ws::Closed> {
+    session.text(serde_json::to_string(event).expect("Events are serializable")).await
+}
+
+/// Instantiations of a job's results, by line number.
+#[get("/results/{id}/instantiations")]
+async fn list_instantiations(registry: web::Data<JobRegistry>, id: web::Path<JobId>, query: web::Query<ItemsQuery>) -> HttpResponse {
+    respond_with_item(&registry, *id, |parser| query::instantiations(parser, &query.list_query()).map(Some))
+}
+
+/// The instantiation at a line of the log.
+#[get("/results/{id}/instantiations/{line}")]
+async fn get_instantiation(registry: web::Data<JobRegistry>, path: web::Path<(JobId, usize)>, query: web::Query<ItemsQuery>) -> HttpResponse {
+    let (id, line) = *path;
+    respond_with_item(&registry, id, |parser| query::instantiation(parser, line, &query.fields()))
+}
+
+/// Terms of a job's results, by namespace and ID.
+#[get("/results/{id}/terms")]
+async fn list_terms(registry: web::Data<JobRegistry>, id: web::Path<JobId>, query: web::Query<ItemsQuery>) -> HttpResponse {
+    respond_with_item(&registry, *id, |parser| query::terms(parser, &query.list_query()).map(Some))
+}
+
+/// The term with an ID, e.g. `12` or `datatype#3` (URL-encoded).
+#[get("/results/{id}/terms/{term_id}")]
+async fn get_term(registry: web::Data<JobRegistry>, path: web::Path<(JobId, String)>, query: web::Query<ItemsQuery>) -> HttpResponse {
+    let (id, term_id) = path.into_inner();
+    respond_with_item(&registry, id, |parser| query::term(parser, &term_id, &query.fields()))
+}
+
+/// Quantifiers of a job's results, by namespace and ID.
+#[get("/results/{id}/quantifiers")]
+async fn list_quantifiers(registry: web::Data<JobRegistry>, id: web::Path<JobId>, query: web::Query<ItemsQuery>) -> HttpResponse {
+    respond_with_item(&registry, *id, |parser| query::quantifiers(parser, &query.list_query()).map(Some))
+}
+
+/// The quantifier with an ID, e.g. `12`.
+#[get("/results/{id}/quantifiers/{quant_id}")]
+async fn get_quantifier(registry: web::Data<JobRegistry>, path: web::Path<(JobId, String)>, query: web::Query<ItemsQuery>) -> HttpResponse {
+    let (id, quant_id) = path.into_inner();
+    respond_with_item(&registry, id, |parser| query::quantifier(parser, &quant_id, &query.fields()))
+}
+
+/// Responds with what `lookup` finds in the results of job `id`:
+/// 404 if there is no such job or item, 409 if the job has no results (yet), 400 if the query is invalid.
+fn respond_with_item<T: Serialize>(registry: &JobRegistry, id: JobId, lookup: impl FnOnce(&Z3Parser1) -> Result<Option<T>, String>) -> HttpResponse {
+    let Some(info) = registry.get(id) else {
+        return HttpResponse::NotFound().finish();
+    };
+    let Some(output) = registry.output(id) else {
+        return HttpResponse::Conflict().body(format!("Job {} has no results: {:?}", id, info.state));
+    };
+    match lookup(&output.parser) {
+        Ok(Some(item)) => HttpResponse::Ok().json(item),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+/// Registers the endpoints.
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::PayloadConfig::new(MAX_LOG_SIZE))
+        .app_data(web::JsonConfig::default().limit(MAX_LOG_SIZE))
+        .service(parse)
+        .service(sample)
+        .service(submit_job)
+        .service(list_jobs)
+        .service(get_job)
+        .service(cancel_job)
+        .service(delete_job)
+        .service(stream_parse)
+        .service(list_instantiations)
+        .service(get_instantiation)
+        .service(list_terms)
+        .service(get_term)
+        .service(list_quantifiers)
+        .service(get_quantifier);
+}
+
+/// Usage: `actix-server [--port PORT]`.
+/// Serves the parser over HTTP on localhost, parsing logs with the settings from the settings file.
+/// CORS is permissive, since the frontend is served from a different port.
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let settings = get_settings();
+    let port = match parse_port(&env::args().skip(1).collect::<Vec<_>>()) {
+        Ok(port) => port,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(());
+        }
+    };
+    if let Err(e) = Logger::from_settings(&settings).and_then(Logger::install) {
+        eprintln!("{}", e);
+        return Ok(());
+    }
+    // parsing writes its outputs there
+    std::fs::create_dir_all("out")?;
+    let registry = web::Data::new(JobRegistry::new());
+    let settings = web::Data::new(settings);
+    log::info!("Listening on http://{}:{}", ADDRESS, port);
+    HttpServer::new(move || {
+        App::new()
+            .wrap(Cors::permissive())
+            .app_data(registry.clone())
+            .app_data(settings.clone())
+            .configure(routes)
+    })
+    .bind((ADDRESS, port))?
+    .run()
+    .await
+}
+
+/// Port given with `--port`, or the default.
+fn parse_port(args: &[String]) -> Result<u16, String> {
+    match args {
+        [] => Ok(DEFAULT_PORT),
+        [option, value] if option == "--port" => value.parse().map_err(|_| format!("Invalid value for --port: {}", value)),
+        _ => Err(String::from("Usage: actix-server [--port PORT]")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prototype::parsers::Z3LogParser;
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+
+    /// A log with a single quantifier and no instantiations.
+    const LOG: &str = "[tool-version] Z3 4.12.1\n[mk-app] #1 a\n[mk-quant] #2 q 1 #1 #1\n";
+    /// A log with a single instantiation, at line 9.
+    const INST_LOG: &str = "[tool-version] Z3 4.12.1
+[mk-app] #1 a
+[mk-var] #2 0
+[mk-app] #3 f #2
+[mk-quant] #4 q1 1 #3 #3
+[attach-var-names] #4 (|x| ; |Int|)
+[mk-app] #5 f #1
+[new-match] 1 #4 #3 #1 ; #5
+[instance] 1 #6 ; 1
+[mk-app] #6 a
+[end-of-instance]
+";
+
+    /// Starts the server on a free localhost port, with jobs that parse without saving or rendering
+    /// and "render" the log's name and number of lines. Logs named "slow" are only parsed once they are cancelled.
+    fn start_server() -> SocketAddr {
+        let (addr_sender, addr) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            actix_web::rt::System::new().block_on(async move {
+                let registry = web::Data::new(JobRegistry::with_runner(|parser, log, settings| {
+                    while settings.file == "slow" && *parser.continue_parsing.lock().unwrap() {
+                        std::thread::yield_now();
+                    }
+                    parser.main_parse_loop(log)?;
+                    Ok(format!("<svg>{}: {} lines</svg>", settings.file, parser.lines_processed()))
+                }));
+                let settings = web::Data::new(Settings::default());
+                let server = HttpServer::new(move || App::new().app_data(registry.clone()).app_data(settings.clone()).configure(routes))
+                    .workers(1)
+                    .bind((ADDRESS, 0))
+                    .unwrap();
+                addr_sender.send(server.addrs()[0]).unwrap();
+                server.run().await
+            })
+        });
+        addr.recv().unwrap()
+    }
+
+    /// Sends an HTTP request and returns the response.
+    fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method, path, body.len(), body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    /// Opens a WebSocket to `path`.
+    fn websocket(addr: SocketAddr, path: &str) -> TcpStream {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+            Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n", path).unwrap();
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0];
+            stream.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+        assert!(head.starts_with(b"HTTP/1.1 101"));
+        stream
+    }
+
+    /// Sends `text` as a single (masked, as required from clients) WebSocket frame.
+    fn send_text(stream: &mut TcpStream, text: &str) {
+        let mut frame = vec![0x81];
+        if text.len() < 126 {
+            frame.push(0x80 | text.len() as u8);
+        } else {
+            frame.push(0x80 | 126);
+            frame.extend((text.len() as u16).to_be_bytes());
+        }
+        // an all-zero mask leaves the payload unchanged
+        frame.extend([0; 4]);
+        frame.extend(text.as_bytes());
+        stream.write_all(&frame).unwrap();
+    }
+
+    /// Reads WebSocket frames until the next text message, which is parsed as JSON. `None` once the socket is closed.
+    fn receive_json(stream: &mut TcpStream) -> Option<serde_json::Value> {
+        loop {
+            let mut head = [0; 2];
+            stream.read_exact(&mut head).unwrap();
+            let len = match head[1] & 0x7f {
+                126 => { let mut len = [0; 2]; stream.read_exact(&mut len).unwrap(); u16::from_be_bytes(len) as usize },
+                127 => { let mut len = [0; 8]; stream.read_exact(&mut len).unwrap(); u64::from_be_bytes(len) as usize },
+                len => len as usize,
+            };
+            let mut payload = vec![0; len];
+            stream.read_exact(&mut payload).unwrap();
+            match head[0] & 0x0f {
+                1 => return Some(serde_json::from_slice(&payload).unwrap()),
+                8 => return None,
+                _ => {},
+            }
+        }
+    }
+
+    /// JSON body of `response`.
+    fn json(response: &str) -> serde_json::Value {
+        serde_json::from_str(&response[response.find("\r\n\r\n").unwrap() + 4..]).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let addr = start_server();
+        let response = request(addr, "POST", "/parse?name=a.log", LOG);
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("content-type: image/svg+xml"));
+        assert!(response.ends_with("<svg>a.log: 3 lines</svg>"));
+        let response = json(&request(addr, "POST", "/parse?format=json", LOG));
+        assert_eq!(response["svg"], "<svg>upload: 3 lines</svg>");
+        assert_eq!(response["state"], "done");
+        assert_eq!(response["dependencies"], serde_json::json!([]));
+        assert!(request(addr, "POST", "/parse?format=png", "x").starts_with("HTTP/1.1 400"));
+        assert!(request(addr, "GET", "/parse", "").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_results() {
+        let addr = start_server();
+        let body = serde_json::json!({ "name": "slow", "text": LOG, "settings": { "quant_graph": false } }).to_string();
+        let response = request(addr, "POST", "/results", &body);
+        assert!(response.starts_with("HTTP/1.1 202"));
+        let id = json(&response)["id"].as_u64().unwrap();
+        let job = json(&request(addr, "GET", &format!("/results/{}", id), ""));
+        assert!(job["state"] == "queued" || job["state"] == "running");
+        assert!(job.get("svg").is_none());
+        assert!(request(addr, "POST", &format!("/results/{}/cancel", id), "").starts_with("HTTP/1.1 200"));
+        let mut job = json(&request(addr, "GET", &format!("/results/{}", id), ""));
+        while job["state"] == "queued" || job["state"] == "running" {
+            job = json(&request(addr, "GET", &format!("/results/{}", id), ""));
+        }
+        assert_eq!(job["state"], "cancelled");
+        assert_eq!(json(&request(addr, "GET", "/results", ""))[0]["id"], id);
+        assert!(request(addr, "DELETE", &format!("/results/{}", id), "").starts_with("HTTP/1.1 200"));
+        assert!(request(addr, "GET", &format!("/results/{}", id), "").starts_with("HTTP/1.1 404"));
+        assert!(request(addr, "POST", "/results", "{ \"text\": \"\", \"settings\": { \"quant_graph\": 1 } }").starts_with("HTTP/1.1 400"));
+        assert!(request(addr, "GET", "/results/100", "").starts_with("HTTP/1.1 404"));
+        assert!(request(addr, "POST", "/results/100/cancel", "").starts_with("HTTP/1.1 404"));
+        assert!(request(addr, "DELETE", "/results/100", "").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_items() {
+        let addr = start_server();
+        let id = json(&request(addr, "POST", "/parse?format=json", LOG))["id"].as_u64().unwrap();
+        let quants = json(&request(addr, "GET", &format!("/results/{}/quantifiers?fields=name,num_vars", id), ""));
+        assert_eq!(quants, serde_json::json!({ "total": 1, "offset": 0, "items": [{ "id": "#2", "data": { "name": "q", "num_vars": 1 } }] }));
+        let term = json(&request(addr, "GET", &format!("/results/{}/terms/1?fields=name", id), ""));
+        assert_eq!(term, serde_json::json!({ "name": "a" }));
+        let terms = json(&request(addr, "GET", &format!("/results/{}/terms?offset=1&limit=1", id), ""));
+        assert_eq!((terms["total"].as_u64(), terms["items"][0]["id"].as_str()), (Some(2), Some("#2")));
+        let insts = json(&request(addr, "GET", &format!("/results/{}/instantiations?quantifier=2", id), ""));
+        assert_eq!(insts["total"], 0);
+        assert!(request(addr, "GET", &format!("/results/{}/terms/1?fields=nope", id), "").starts_with("HTTP/1.1 400"));
+        assert!(request(addr, "GET", &format!("/results/{}/instantiations/3", id), "").starts_with("HTTP/1.1 404"));
+        assert!(request(addr, "GET", "/results/100/terms", "").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_stream() {
+        let addr = start_server();
+        let mut socket = websocket(addr, "/stream");
+        send_text(&mut socket, &serde_json::json!({ "name": "inst.log", "text": INST_LOG }).to_string());
+        let mut events = vec![];
+        while let Some(event) = receive_json(&mut socket) {
+            events.push(event);
+        }
+        assert_eq!(events[0]["event"], "started");
+        let batch = events.iter().find(|event| event["event"] == "batch").unwrap();
+        assert_eq!(batch["instantiations"][0]["line_no"], 9);
+        let finished = events.last().unwrap();
+        assert_eq!((&finished["event"], &finished["state"], &finished["progress"]["lines_read"]), (&"finished".into(), &"done".into(), &11.into()));
+
+        let mut socket = websocket(addr, "/stream");
+        send_text(&mut socket, &serde_json::json!({ "name": "slow", "text": LOG }).to_string());
+        assert_eq!(receive_json(&mut socket).unwrap()["event"], "started");
+        send_text(&mut socket, "stop");
+        let mut last = None;
+        while let Some(event) = receive_json(&mut socket) {
+            last = Some(event);
+        }
+        assert_eq!(last.unwrap()["state"], "cancelled");
+
+        let mut socket = websocket(addr, "/stream");
+        send_text(&mut socket, "{}");
+        assert_eq!(receive_json(&mut socket).unwrap()["event"], "error");
+        assert!(receive_json(&mut socket).is_none());
+    }
+
+    #[test]
+    fn test_parse_port() {
+        assert_eq!(parse_port(&[]), Ok(DEFAULT_PORT));
+        assert_eq!(parse_port(&[String::from("--port"), String::from("3000")]), Ok(3000));
+        assert!(parse_port(&[String::from("--port"), String::from("x")]).is_err());
+    }
+}
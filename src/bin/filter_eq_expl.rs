@@ -1,6 +1,6 @@
 use std::{collections::BTreeMap, fs};
 
-use prototype::{file_io, items::{self, EqualityExpl, BlamedTermItem, Instantiation}};
+use prototype::{file_io, items::{self, EqualityExpl, BlamedTermItem, Instantiation}, parsers::parse_fingerprint};
 
 /// Just for testing "cg" equality explanations and finding the explanations they depend on.
 fn main() {
@@ -79,7 +79,7 @@ fn main() {
                 .map(|&t| String::from(t))
                 .collect();
             let mut blamed_terms: Vec<BlamedTermItem> = vec![];
-            let fingerprint = u64::from_str_radix(l[1], 16).expect("Should be valid hex string");
+            let fingerprint = parse_fingerprint(l[1]);
             // fingerprints.insert(line_no + 1, fingerprint);
             let quant_id = l[2];
             let pattern_id = l[3];
@@ -0,0 +1,106 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+
+use crate::items::{Term, Quantifier, Instantiation, Dependency, EqualityExpl, TwoDMap};
+
+/// Identifies binary export files
+pub const EXPORT_MAGIC: &[u8; 8] = b"APEXPORT";
+/// Binary export schema version; must be increased whenever the layout of `ExportData` or the exported items changes
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Parsed results as stored in a binary export file, loaded with `load_binary`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportData {
+    pub terms: TwoDMap<Term>,
+    pub quantifiers: TwoDMap<Quantifier>,
+    pub instantiations: BTreeMap<usize, Instantiation>,
+    pub dependencies: Vec<Dependency>,
+    pub eq_expls: BTreeMap<String, EqualityExpl>,
+}
+
+/// Borrowed view of parsed results used for writing, so that nothing has to be cloned.
+/// Field order must match `ExportData`.
+#[derive(Serialize)]
+pub struct ExportDataRef<'a> {
+    pub terms: &'a TwoDMap<Term>,
+    pub quantifiers: &'a TwoDMap<Quantifier>,
+    pub instantiations: &'a BTreeMap<usize, Instantiation>,
+    pub dependencies: &'a [Dependency],
+    pub eq_expls: &'a BTreeMap<String, EqualityExpl>,
+}
+
+/// Writes a header of `magic` and the little-endian format `version`, followed by the bincode-encoded `data`, to `writer`.
+/// Used for binary exports and parser snapshots.
+pub fn write_versioned<W: Write, T: Serialize + ?Sized>(mut writer: W, magic: &[u8; 8], version: u32, data: &T) -> Result<(), String> {
+    writer.write_all(magic).map_err(|e| e.to_string())?;
+    writer.write_all(&version.to_le_bytes()).map_err(|e| e.to_string())?;
+    bincode::serialize_into(&mut writer, data).map_err(|e| e.to_string())?;
+    writer.flush().map_err(|e| e.to_string())
+}
+
+/// Reads data written by `write_versioned` with the same `magic` and `version` from `reader`.
+/// `kind` names the format in errors, e.g. `"binary export"`.
+/// # Errors
+/// Errors if the data does not start with `magic`, has a different format version, or is malformed.
+pub fn read_versioned<R: Read, T: DeserializeOwned>(mut reader: R, magic: &[u8; 8], version: u32, kind: &str) -> Result<T, String> {
+    let mut header = [0; 8];
+    let mut header_version = [0; 4];
+    reader.read_exact(&mut header).map_err(|e| e.to_string())?;
+    reader.read_exact(&mut header_version).map_err(|e| e.to_string())?;
+    if &header != magic {
+        return Err(format!("Not a {} file", kind));
+    }
+    let header_version = u32::from_le_bytes(header_version);
+    if header_version != version {
+        return Err(format!("The {} version {} is not supported (expected {})", kind, header_version, version));
+    }
+    bincode::deserialize_from(reader).map_err(|e| e.to_string())
+}
+
+/// Writes the schema header followed by the bincode-encoded `data` to `writer`.
+pub fn write_binary<W: Write>(writer: W, data: &ExportDataRef) -> Result<(), String> {
+    write_versioned(writer, EXPORT_MAGIC, EXPORT_VERSION, data)
+}
+
+/// Reads parsed results written by `write_binary` from `reader`.
+/// # Errors
+/// Errors if the data does not start with the export header, has a different schema version, or is malformed.
+pub fn read_binary<R: Read>(reader: R) -> Result<ExportData, String> {
+    read_versioned(reader, EXPORT_MAGIC, EXPORT_VERSION, "binary export")
+}
+
+/// Saves parsed results to the binary export file `filename`.
+pub fn save_binary(filename: &str, data: &ExportDataRef) -> Result<(), String> {
+    let file = File::create(filename).map_err(|e| format!("Error opening {}: {}", filename, e))?;
+    write_binary(io::BufWriter::new(file), data)
+}
+
+/// Loads parsed results from the binary export file `filename`.
+pub fn load_binary(filename: &str) -> Result<ExportData, String> {
+    let file = File::open(filename).map_err(|e| format!("Error opening {}: {}", filename, e))?;
+    read_binary(io::BufReader::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{test_logs::{self, parse}, Log, LogParser};
+
+    #[test]
+    fn test_binary_export_round_trip() {
+        let path = std::env::temp_dir().join(format!("export_test_{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+        let parser = parse(Log::File(test_logs::SIMPLE.to_string()));
+        parser.export_binary(path).unwrap();
+        let data = load_binary(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(data.instantiations.keys().collect::<Vec<_>>(), parser.instantiations().keys().collect::<Vec<_>>());
+        assert_eq!(data.dependencies.len(), parser.get_dependencies().len());
+        assert_eq!(data.terms.get("#8"), parser.terms().get("#8"));
+        assert_eq!(data.quantifiers.get("#5"), parser.quantifiers().get("#5"));
+        assert_eq!(&data.eq_expls, parser.eq_expls());
+    }
+}
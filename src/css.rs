@@ -1,45 +1,147 @@
 /// Private module for generating colors
 mod colors {
-    /// Generate `n` distinct colors in HSL format; each color represents instantiations from the same quantifier.
-    pub fn make_hsl_strings(n: usize) -> Vec<String> {
-        const DEFAULT_SAT: f64 = 100.0;
-        const DEFAULT_LUM: f64 = 70.0;
-        generate_colors(n).iter().map(|hue| 
-            format!("hsl({}, {}%, {}%)", hue, DEFAULT_SAT, DEFAULT_LUM)).collect::<Vec<String>>()
-    }
-
-    /// Generate `n` distinct colors (hue values) from golden ratio; essentially pseudorandomly with fixed seed.
-    /// 
-    /// Explained here: https://martin.ankerl.com/2009/12/09/how-to-create-random-colors-programmatically/
-    /// ### Note
-    /// Colors became hard to tell apart once `n` gets large enough (> 10?)
-    fn generate_colors(n: usize) -> Vec<f64> {
-        const GOLDEN_RATIO_RECIPROCAL: f64 = 0.618033988749895;
-        let mut hue = 0.0;
-        let mut result = vec![hue];
-        for _ in 1..n {
-            hue += GOLDEN_RATIO_RECIPROCAL;
-            hue = hue.fract() * 360.0;
-            result.push(hue);
+    use crate::parsers::{fnv1a, FNV_OFFSET_BASIS};
+
+    /// Lightness levels (OKLCH) that colors alternate between; all light enough for black labels.
+    const LIGHTNESS_LEVELS: [f64; 3] = [0.88, 0.80, 0.72];
+    /// Chroma levels (OKLCH) that colors alternate between.
+    const CHROMA_LEVELS: [f64; 2] = [0.09, 0.15];
+
+    /// Color (as `#rrggbb`) for the quantifier named `name`, derived from a hash of the name only,
+    /// so that a quantifier has the same color in every run and in the graphs of different logs.
+    ///
+    /// The hue varies continuously with the hash, while lightness and saturation alternate between a few levels,
+    /// so that quantifiers with similar hues are still told apart. Colors are spaced in the perceptually uniform
+    /// OKLCH color space, where equal steps in hue look like equal changes in color.
+    pub fn quant_color(name: &str) -> String {
+        let hash = stable_hash(name);
+        let hue = (hash & 0xffff) as f64 / 65536.0 * 360.0;
+        let lightness = LIGHTNESS_LEVELS[((hash >> 16) % LIGHTNESS_LEVELS.len() as u64) as usize];
+        let chroma = CHROMA_LEVELS[((hash >> 24) % CHROMA_LEVELS.len() as u64) as usize];
+        oklch_to_hex(lightness, chroma, hue)
+    }
+
+    /// 64-bit FNV-1a hash of `s`, which (unlike the standard library's hashers) is the same across Rust versions.
+    fn stable_hash(s: &str) -> u64 {
+        fnv1a(FNV_OFFSET_BASIS, s.bytes())
+    }
+
+    /// Converts an OKLCH color to `#rrggbb`, reducing the chroma until the color is within the sRGB gamut.
+    ///
+    /// Explained here: https://bottosson.github.io/posts/oklab/
+    fn oklch_to_hex(lightness: f64, chroma: f64, hue: f64) -> String {
+        let mut chroma = chroma;
+        loop {
+            let (a, b) = (chroma * hue.to_radians().cos(), chroma * hue.to_radians().sin());
+            let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+            let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+            let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+            let rgb = [
+                4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+                -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+                -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+            ];
+            if chroma <= 0.0 || rgb.iter().all(|c| (0.0..=1.0).contains(c)) {
+                let [r, g, b] = rgb.map(|c| (gamma(c.clamp(0.0, 1.0)) * 255.0).round() as u8);
+                return format!("#{:02x}{:02x}{:02x}", r, g, b);
+            }
+            chroma -= 0.005;
         }
-        result
+    }
+
+    /// sRGB transfer function, from linear to gamma-encoded intensity.
+    fn gamma(c: f64) -> f64 {
+        if c <= 0.0031308 { 12.92 * c } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
     }
 }
 
 pub mod make_css {
-    use std::collections::HashSet;
+    use std::collections::BTreeMap;
+    use serde::Serialize;
     use super::colors::*;
+    use crate::quant_filter::glob_match;
     const NODE_HOVER_RULE: &str = ".node:hover {\n\topacity: 0.6\n}\n\n";
     const EDGE_HOVER_RULE: &str = ".edge:hover * {\n\topacity: 0.4\n}\n\n";
-    /// From given set of quantifier names, generate a CSS style sheet as a string: 
+
+    /// A quantifier shown in a graph and its color.
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct LegendEntry {
+        /// full ID, e.g. `#12`
+        pub id: String,
+        pub name: String,
+        /// CSS color
+        pub color: String,
+    }
+
+    impl LegendEntry {
+        /// CSS class of the graph nodes of this quantifier.
+        pub fn class(&self) -> String {
+            String::from("quant_") + &self.id.replace('#', "")
+        }
+    }
+
+    /// Colors for the quantifiers in `quants` (names by ID), sorted by name.
+    /// A quantifier gets the color in `pinned` whose key matches its ID or name (`*` and `?` can be used as wildcards,
+    /// the first matching key is used), otherwise one derived from its name.
+    /// Pinned colors that are not plain CSS color values are ignored.
+    pub fn make_legend(quants: &BTreeMap<String, String>, pinned: &BTreeMap<String, String>) -> Vec<LegendEntry> {
+        let mut legend: Vec<LegendEntry> = quants.iter()
+            .map(|(id, name)| {
+                let color = pinned.iter()
+                    .find(|(pattern, color)| (glob_match(pattern, id) || glob_match(pattern, name)) && is_color_value(color))
+                    .map_or_else(|| quant_color(name), |(_, color)| color.clone());
+                LegendEntry { id: id.clone(), name: name.clone(), color }
+            })
+            .collect();
+        legend.sort_by(|a, b| (&a.name, &a.id).cmp(&(&b.name, &b.id)));
+        legend
+    }
+
+    /// Whether `s` can be used as a color in a style sheet without changing its structure,
+    /// e.g. `#ff8800`, `orange` or `rgb(255, 136, 0)`.
+    fn is_color_value(s: &str) -> bool {
+        !s.trim().is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || " #(),.%-".contains(c))
+    }
+
+    /// From given legend, generate a CSS style sheet as a string:
     /// fixed rules for SVG nodes and edges
     /// followed by a fill for each ellipse class (representing one quantifier and color).
-    pub fn make_css_string(quants: &HashSet<String>) -> String {
+    pub fn make_css_string(legend: &[LegendEntry]) -> String {
         let mut result = String::from(NODE_HOVER_RULE) + EDGE_HOVER_RULE;
-        let rgb_strings = make_hsl_strings(quants.len());
-        for (i, quant) in quants.iter().enumerate() {
-            result += &format!(".{} ellipse {{\n\tfill: {} !important\n}}\n\n", quant, rgb_strings[i]);
+        for entry in legend {
+            result += &format!("/* {} */\n.{} ellipse {{\n\tfill: {} !important\n}}\n\n",
+                entry.name.replace("*/", "* /"), entry.class(), entry.color);
         }
         result
     }
-}
\ No newline at end of file
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_make_legend() {
+            let quants = BTreeMap::from([
+                (String::from("#5"), String::from("q1")),
+                (String::from("#6"), String::from("prelude_len")),
+                (String::from("arith#"), String::from("arith#")),
+            ]);
+            let legend = make_legend(&quants, &BTreeMap::new());
+            assert_eq!(legend.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["arith#", "#6", "#5"]);
+            // colors depend only on the name
+            assert_eq!(legend[2].color, quant_color("q1"));
+            assert!(legend.iter().all(|e| e.color.len() == 7 && e.color.starts_with('#')));
+            assert_ne!(legend[1].color, legend[2].color);
+            let pinned = BTreeMap::from([
+                (String::from("prelude_*"), String::from("rgb(255, 0, 0)")),
+                (String::from("#5"), String::from("red; } svg { display: none")),
+            ]);
+            let legend = make_legend(&quants, &pinned);
+            assert_eq!(legend[1].color, "rgb(255, 0, 0)");
+            assert_eq!(legend[2].color, quant_color("q1"));
+            let css = make_css_string(&legend);
+            assert!(css.contains("/* q1 */\n.quant_5 ellipse {\n\tfill: "));
+            assert!(css.contains(".quant_arith ellipse"));
+        }
+    }
+}
@@ -1,5 +1,30 @@
-use std::{io::Write, collections::{BTreeSet, HashSet}};
-use crate::{file_io::open_file_truncate, items::Dependency};
+use std::{io::Write, collections::{BTreeMap, BTreeSet, HashSet}};
+use crate::{css::make_css::{self, LegendEntry}, file_io::{open_file_truncate, NodeSize, Settings}, items::{DepType, Dependency}, quant_graph::QuantGraph, subgraph::Subgraph};
+
+/// largest node size step, reached at a cost or generation of 2^MAX_SIZE_STEP
+const MAX_SIZE_STEP: u32 = 6;
+/// Graphviz's default node size, in inches
+const DEFAULT_WIDTH: f32 = 0.75;
+const DEFAULT_HEIGHT: f32 = 0.5;
+/// number of instantiations shown in the instantiation graph
+const FILTER_LIMIT: usize = 250;    // TODO: make this a setting
+
+/// Details of an instantiation shown on its node.
+pub struct NodeInfo {
+    pub quant_name: String,
+    /// where the quantifier is written in the SMT2 file and its triggers, empty if unknown
+    pub source: String,
+    pub cost: f32,
+    pub z3_gen: u32,
+}
+
+/// Source of the details shown on the nodes and edges of the instantiation graph.
+pub trait GraphInfo {
+    /// Details of the instantiation at line `line_no`, if it is known.
+    fn node_info(&self, line_no: usize) -> Option<NodeInfo>;
+    /// Pretty-printed text of the term with ID `id`.
+    fn term_text(&self, id: &str) -> String;
+}
 
 /// A node representing a quantifier instantiation.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
@@ -8,121 +33,280 @@ struct InstNode {
     quant: String
 }
 
-/// Dot representation for each node.
-impl std::fmt::Display for InstNode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl InstNode {
+    /// Dot representation of the node: labelled with its quantifier and cost, sized according to `node_size`,
+    /// and linking to its own anchor (which the HTML report uses to show the details of the instantiation).
+    fn to_dot(&self, info: &dyn GraphInfo, node_size: &NodeSize) -> String {
         let quant_id = self.quant.replace('#', "");
-        write!(f, "{} [ class=\"quant_{}\", tooltip=\"quant_{}\" ]", self.line_no, quant_id, self.quant)
+        let anchor = format!("inst-{}", self.line_no);
+        let node = info.node_info(self.line_no);
+        let mut tooltip = format!("quant_{}", self.quant);
+        if let Some(node) = node.as_ref().filter(|node| !node.source.is_empty()) {
+            tooltip += &format!("\n{}", node.source);
+        }
+        let mut result = format!("{} [ class=\"quant_{}\", tooltip=\"{}\", id=\"{}\", URL=\"#{}\"",
+            self.line_no, quant_id, escape_dot(&tooltip), anchor, anchor);
+        if let Some(node) = node {
+            let label = format!("@{}\n{}\ncost {:.1}", self.line_no, node.quant_name, node.cost);
+            result += &format!(", label=\"{}\"", escape_dot(&label));
+            let value = match node_size {
+                NodeSize::Cost => node.cost,
+                NodeSize::Generation => node.z3_gen as f32 + 1.0,
+                NodeSize::Fixed => 1.0,
+            };
+            result += &size_attrs(value);
+        }
+        result + " ]"
+    }
+}
+
+/// Dot attributes for the size of a node showing `value`, empty for values up to 1.
+/// The size grows with the logarithm of the value, so that a few very large values do not dominate the graph.
+fn size_attrs(value: f32) -> String {
+    let step = value.max(1.0).log2().floor().min(MAX_SIZE_STEP as f32) as u32;
+    if step == 0 {
+        return String::new();
     }
+    let scale = 1.0 + 0.25 * step as f32;
+    format!(", width={:.2}, height={:.2}", DEFAULT_WIDTH * scale, DEFAULT_HEIGHT * scale)
 }
 
 /// An edge representing a dependency from one QI to another.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
 struct InstEdge {
     node2: usize,
-    node1: usize
+    node1: usize,
+    /// `DepType::Term` if any of the dependencies between the two nodes is a match on terms
+    dep_type: DepType,
+    /// IDs of the blamed terms of all dependencies between the two nodes
+    blamed: Vec<String>,
 }
 
-/// Dot representation for each edge.
-impl std::fmt::Display for InstEdge {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} -> {} [ ]", self.node1, self.node2)
+impl InstEdge {
+    /// Dot representation of the edge: dependencies on matched terms are solid, on equalities dashed,
+    /// with the blamed terms as tooltip.
+    fn to_dot(&self, info: &dyn GraphInfo) -> String {
+        let style = match self.dep_type {
+            DepType::Term => "solid",
+            DepType::Equality => "dashed",
+            DepType::None => "dotted",
+        };
+        let blamed: Vec<String> = self.blamed.iter().map(|id| info.term_text(id)).collect();
+        format!("{} -> {} [ style={}, tooltip=\"{}\" ]", self.node1, self.node2, style, escape_dot(&blamed.join("\n")))
     }
 }
 
-/// Output Dot representation for entire QI graph.
-fn get_dot(name: &str, node_list: &BTreeSet<InstNode>, edge_list: &BTreeSet<InstEdge>) -> String {
+/// Escapes text for use in a quoted Dot string, turning newlines into Dot line breaks.
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Numbers of instantiations depending on or depended on by an instantiation in the graph that were left out of it.
+#[derive(Default, Debug, PartialEq)]
+struct CutNeighbours {
+    parents: usize,
+    children: usize,
+}
+
+/// Instantiation graph in which every edge connects two of its nodes.
+#[derive(Default)]
+struct InstGraph {
+    nodes: BTreeSet<InstNode>,
+    edges: BTreeSet<InstEdge>,
+    /// neighbours left out of the graph by line number of the node they are neighbours of,
+    /// shown as one collapsed placeholder node for the parents and one for the children of that node
+    cut: BTreeMap<usize, CutNeighbours>,
+}
+
+impl InstGraph {
+    /// Graph of the `max_nodes` earliest instantiations in `dependencies` and the dependencies between them.
+    /// Dependencies on or of instantiations left out are dropped, or (with `cut_neighbours`) counted in `cut`.
+    fn build(dependencies: &Vec<Dependency>, max_nodes: usize, cut_neighbours: bool) -> InstGraph {
+        let (nodes, edges) = build_graph(dependencies);
+        let nodes: BTreeSet<InstNode> = nodes.into_iter().take(max_nodes).collect();
+        let line_nos: HashSet<usize> = nodes.iter().map(|node| node.line_no).collect();
+        let mut graph = InstGraph { nodes, ..InstGraph::default() };
+        for edge in edges {
+            match (line_nos.contains(&edge.node1), line_nos.contains(&edge.node2)) {
+                (true, true) => {
+                    graph.edges.insert(edge);
+                },
+                (false, true) if cut_neighbours => graph.cut.entry(edge.node2).or_default().parents += 1,
+                (true, false) if cut_neighbours => graph.cut.entry(edge.node1).or_default().children += 1,
+                _ => (),
+            }
+        }
+        graph
+    }
+}
+
+/// Dot representation of the placeholder nodes for the `cut` neighbours of the node at line `line_no`, and their edges.
+fn cut_dot(line_no: usize, cut: &CutNeighbours) -> String {
+    let mut result = String::new();
+    if cut.parents > 0 {
+        result += &format!("\t\"cut-to-{l}\" [ class=\"cut\", label=\"…\", tooltip=\"{} more instantiations leading to @{l}\", style=dashed ]\n", cut.parents, l = line_no);
+        result += &format!("\t\"cut-to-{l}\" -> {l} [ style=dotted ]\n", l = line_no);
+    }
+    if cut.children > 0 {
+        result += &format!("\t\"cut-from-{l}\" [ class=\"cut\", label=\"…\", tooltip=\"{} more instantiations caused by @{l}\", style=dashed ]\n", cut.children, l = line_no);
+        result += &format!("\t{l} -> \"cut-from-{l}\" [ style=dotted ]\n", l = line_no);
+    }
+    result
+}
+
+/// Output Dot representation for entire QI graph, drawing the outline of node `highlight` (if any) thicker.
+fn get_dot(name: &str, graph: &InstGraph, info: &dyn GraphInfo, node_size: &NodeSize, highlight: Option<usize>) -> String {
     let mut result = String::from("digraph ") + name + " {\n";
-    for node in node_list {
-        result += &format!("\t{}\n", node);
+    for node in &graph.nodes {
+        let node_dot = node.to_dot(info, node_size);
+        match node_dot.strip_suffix(" ]").filter(|_| highlight == Some(node.line_no)) {
+            Some(attrs) => result += &format!("\t{}, penwidth=3 ]\n", attrs),
+            None => result += &format!("\t{}\n", node_dot),
+        }
     }
-    for edge in edge_list {
-        result += &format!("\t{}\n", edge);
+    for edge in &graph.edges {
+        result += &format!("\t{}\n", edge.to_dot(info));
+    }
+    for (line_no, cut) in &graph.cut {
+        result += &cut_dot(*line_no, cut);
     }
     result += "}\n";
     result
 
 }
 
-/// Filter out theory-solving instantiations
-fn filter_theory_inst(_dep: &Dependency) -> bool {
-    _dep.quant != "arith#" && _dep.quant != "basic#"
-    // true
+/// Output Dot representation of the quantifier graph: nodes are sized by number of instances,
+/// edges labelled with the number of instantiations of one quantifier that triggered instantiations of another.
+pub fn get_quant_dot(graph: &QuantGraph) -> String {
+    let mut result = String::from("digraph quantifiers {\n");
+    for node in &graph.nodes {
+        let label = format!("{}\n{} instances\ncost {:.1}", node.name, node.instances, node.cost);
+        result += &format!("\t\"{}\" [ class=\"quant_{}\", tooltip=\"{}\", label=\"{}\"{} ]\n",
+            escape_dot(&node.id), node.id.replace('#', ""), escape_dot(&node.id), escape_dot(&label), size_attrs(node.instances as f32));
+    }
+    for edge in &graph.edges {
+        let penwidth = 1.0 + (edge.count as f32).log2().min(MAX_SIZE_STEP as f32);
+        result += &format!("\t\"{}\" -> \"{}\" [ label=\"{}\", tooltip=\"{} -> {}: {}\", penwidth={:.1} ]\n",
+            escape_dot(&edge.from), escape_dot(&edge.to), edge.count, escape_dot(&edge.from), escape_dot(&edge.to), edge.count, penwidth);
+    }
+    result += "}\n";
+    result
+}
+
+/// Write Dot string of the quantifier graph to file.
+pub fn output_quant_dot_to_file(filename: &str, graph: &QuantGraph) {
+    let mut file = open_file_truncate(filename);
+    file.write_all(get_quant_dot(graph).as_bytes()).expect("failed to write dot file");
 }
 
 /// Get nodes and edges from dependency list.
+/// Instantiations are not filtered here: `dependencies` should only contain those to be shown
+/// (see `quant_filter::QuantFilter`).
 fn build_graph(dependencies: &Vec<Dependency>) -> (BTreeSet<InstNode>, BTreeSet<InstEdge>) {
-    let filter: fn(&Dependency) -> bool = filter_theory_inst;
     let mut node_list = BTreeSet::new();
-    let mut edge_list = BTreeSet::new();
+    let mut edges = BTreeMap::new();
     for dep in dependencies {
-        if filter(dep) {
-            if dep.from != 0 {
-                edge_list.insert(InstEdge { node1: dep.from, node2: dep.to });
-            }
-            node_list.insert(InstNode { line_no: dep.to, quant: dep.quant.clone() });
-        }
+        add_edge(&mut edges, dep);
+        node_list.insert(InstNode { line_no: dep.to, quant: dep.quant.clone() });
     }
-    (node_list, edge_list)
+    (node_list, edges.into_values().collect())
 }
 
-/// Filter `node_list` and `edge_list` so that only the earliest instantiations are displayed.
-fn filter_graph_line_no(node_list: BTreeSet<InstNode>, edge_list: BTreeSet<InstEdge>) -> (BTreeSet<InstNode>, BTreeSet<InstEdge>) {
-    const FILTER_LIMIT: usize = 250;    // TODO: make this a setting
-    let num_nodes_to_get = std::cmp::min(FILTER_LIMIT, node_list.len());
-    let node_list_result = Vec::from_iter(node_list)[..num_nodes_to_get].to_vec();
-    let edge_list_result = Vec::from_iter(edge_list);
+/// Adds an edge for `dep` to `edges`, merging dependencies between the same two instantiations into one edge.
+fn add_edge(edges: &mut BTreeMap<(usize, usize), InstEdge>, dep: &Dependency) {
+    if dep.from == 0 {
+        return;
+    }
+    let edge = edges.entry((dep.to, dep.from))
+        .or_insert_with(|| InstEdge { node1: dep.from, node2: dep.to, dep_type: dep.dep_type.clone(), blamed: vec![] });
+    if dep.dep_type == DepType::Term {
+        edge.dep_type = DepType::Term;
+    }
+    if !dep.blamed.is_empty() && !edge.blamed.contains(&dep.blamed) {
+        edge.blamed.push(dep.blamed.clone());
+    }
+}
 
-    // finds "index" of the dependency (edge) with the last included instantiation line number
-    // uses property that instantiations are sorted by "to" inst ID
-    // include all dependencies up to that one
-    let index = edge_list_result.iter()
-    .map(|e| e.node2)
-    .collect::<Vec<usize>>()
-    .binary_search(&node_list_result.last().unwrap().line_no) // unwrap can fail
-    .unwrap_or_else(|index| index);
-    let index = std::cmp::min(index + 1, edge_list_result.len());
-    (BTreeSet::from_iter(node_list_result), BTreeSet::from_iter(edge_list_result[..index].to_owned()))
+/// Output Dot representation of `subgraph`, with its center highlighted.
+/// Unlike the full graph, no instantiations are filtered out, since any of them may be part of the chain leading to the center.
+pub fn get_subgraph_dot(subgraph: &Subgraph, info: &dyn GraphInfo, node_size: &NodeSize) -> String {
+    let nodes = subgraph.nodes.iter()
+        .map(|(line_no, node)| InstNode { line_no: *line_no, quant: node.quant.clone() })
+        .collect();
+    let mut edges = BTreeMap::new();
+    for dep in &subgraph.dependencies {
+        add_edge(&mut edges, dep);
+    }
+    let graph = InstGraph { nodes, edges: edges.into_values().collect(), ..InstGraph::default() };
+    get_dot("subgraph", &graph, info, node_size, Some(subgraph.center))
+}
+
+/// Write Dot string of `subgraph` to file.
+pub fn output_subgraph_dot_to_file(filename: &str, subgraph: &Subgraph, info: &dyn GraphInfo, node_size: &NodeSize) {
+    let mut file = open_file_truncate(filename);
+    file.write_all(get_subgraph_dot(subgraph, info, node_size).as_bytes()).expect("failed to write dot file");
 }
 
 /// Write Dot string of instantiation graph to file, and then a CSS string for quantifier colors.
-pub fn output_dot_and_css_to_file(dot_filename: &str, css_filename: &str, dependencies: &Vec<Dependency>) {
-    let node_list = output_dot_to_file(dot_filename, dependencies);
-    output_css_to_file(css_filename, &node_list);
+pub fn output_dot_and_css_to_file(dot_filename: &str, css_filename: &str, dependencies: &Vec<Dependency>, info: &dyn GraphInfo, settings: &Settings) {
+    let graph = InstGraph::build(dependencies, FILTER_LIMIT, settings.cut_neighbours);
+    let mut file = open_file_truncate(dot_filename);
+    file.write_all(&get_dot("instantiations", &graph, info, &settings.node_size, None).into_bytes()).expect("failed to write dot file");
+    output_css_to_file(css_filename, &get_legend_of_nodes(&graph.nodes, info, settings));
 }
 
-pub fn get_dot_output_as_string(dependencies: &Vec<Dependency>) -> String {
-    let (node_list, edge_list) = build_graph(dependencies);
-    let (node_list, edge_list) = filter_graph_line_no(node_list, edge_list);
-    get_dot("instantiations", &node_list, &edge_list)
+pub fn get_dot_output_as_string(dependencies: &Vec<Dependency>, info: &dyn GraphInfo, settings: &Settings) -> String {
+    let graph = InstGraph::build(dependencies, FILTER_LIMIT, settings.cut_neighbours);
+    get_dot("instantiations", &graph, info, &settings.node_size, None)
 }
 
-// Turn `dependencies` into a Dot file
-fn output_dot_to_file(filename: &str, dependencies: &Vec<Dependency>) -> BTreeSet<InstNode> {
-    let mut file = open_file_truncate(filename);
-    let (node_list, edge_list) = build_graph(dependencies);
-    let (node_list, edge_list) = filter_graph_line_no(node_list, edge_list);
-    file.write_all(&get_dot("instantiations", &node_list, &edge_list).into_bytes()).expect("failed to write dot file");
-    node_list
+/// Get CSS string of quantifier colors for the graph of `dependencies`.
+pub fn get_css_output_as_string(dependencies: &Vec<Dependency>, info: &dyn GraphInfo, settings: &Settings) -> String {
+    make_css::make_css_string(&get_legend(dependencies, info, settings))
+}
+
+/// Get the quantifiers in the graph of `dependencies` and their colors.
+pub fn get_legend(dependencies: &Vec<Dependency>, info: &dyn GraphInfo, settings: &Settings) -> Vec<LegendEntry> {
+    get_legend_of_nodes(&InstGraph::build(dependencies, FILTER_LIMIT, false).nodes, info, settings)
+}
+
+/// Get the quantifiers of the nodes in `node_list` and their colors, pinned by `settings.quantifier_colors`.
+fn get_legend_of_nodes(node_list: &BTreeSet<InstNode>, info: &dyn GraphInfo, settings: &Settings) -> Vec<LegendEntry> {
+    let mut quants = BTreeMap::new();
+    for node in node_list {
+        if !quants.contains_key(&node.quant) {
+            let name = info.node_info(node.line_no).map_or_else(|| node.quant.clone(), |node| node.quant_name);
+            quants.insert(node.quant.clone(), name);
+        }
+    }
+    make_css::make_legend(&quants, &settings.quantifier_colors)
 }
 
-/// Build CSS file from `node_list`
-fn output_css_to_file(filename: &str, node_list: &BTreeSet<InstNode>) {
-    use crate::css::make_css;
+/// Build CSS file from `legend`
+fn output_css_to_file(filename: &str, legend: &[LegendEntry]) {
     let mut file = open_file_truncate(filename);
-    let quant_set: HashSet<String> = node_list.iter()
-    .map(|node| String::from("quant_") + &node.quant.replace('#', ""))
-    .collect();
-    let css = make_css::make_css_string(&quant_set);
-    file.write_all(&css.into_bytes()).expect("failed to write CSS file");
+    file.write_all(&make_css::make_css_string(legend).into_bytes()).expect("failed to write CSS file");
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Knows only the instantiation at line 2 and the term `#8`.
+    struct TestInfo;
+
+    impl GraphInfo for TestInfo {
+        fn node_info(&self, line_no: usize) -> Option<NodeInfo> {
+            (line_no == 2).then(|| NodeInfo { quant_name: String::from("q\"B\""), source: String::new(), cost: 5.0, z3_gen: 0 })
+        }
+
+        fn term_text(&self, id: &str) -> String {
+            if id == "#8" { String::from("f[8](a[1])") } else { id.to_string() }
+        }
+    }
+
     #[test]
-    fn test_node_display() {
+    fn test_node_dot() {
         let node1 = InstNode {
             line_no: 1,
             quant: "#A".to_string()
@@ -131,17 +315,34 @@ mod tests {
             line_no: 2,
             quant: "ns#B".to_string()
         };
-        assert_eq!(&format!("{}", node1), "1 [ class=\"quant_A\", tooltip=\"quant_#A\" ]");
-        assert_eq!(&format!("{}", node2), "2 [ class=\"quant_nsB\", tooltip=\"quant_ns#B\" ]");
+        assert_eq!(&node1.to_dot(&TestInfo, &NodeSize::Cost), "1 [ class=\"quant_A\", tooltip=\"quant_#A\", id=\"inst-1\", URL=\"#inst-1\" ]");
+        assert_eq!(&node2.to_dot(&TestInfo, &NodeSize::Cost),
+            "2 [ class=\"quant_nsB\", tooltip=\"quant_ns#B\", id=\"inst-2\", URL=\"#inst-2\", label=\"@2\\nq\\\"B\\\"\\ncost 5.0\", width=1.12, height=0.75 ]");
+        assert!(!node2.to_dot(&TestInfo, &NodeSize::Generation).contains("width"));
+        assert!(!node2.to_dot(&TestInfo, &NodeSize::Fixed).contains("width"));
     }
 
     #[test]
-    fn test_edge_display() {
+    fn test_edge_dot() {
         let edge = InstEdge {
             node1: 100,
-            node2: 200
+            node2: 200,
+            dep_type: DepType::Equality,
+            blamed: vec![String::from("#8"), String::from("#9")],
         };
-        assert_eq!(&format!("{}", edge), "100 -> 200 [ ]");
+        assert_eq!(&edge.to_dot(&TestInfo), "100 -> 200 [ style=dashed, tooltip=\"f[8](a[1])\\n#9\" ]");
+    }
+
+    #[test]
+    fn test_build_graph_merges_edges() {
+        let dep = |from, blamed: &str, dep_type| Dependency { from, to: 2, blamed: blamed.to_string(), dep_type, quant: String::from("#B") };
+        let deps = vec![dep(1, "#8", DepType::Equality), dep(1, "#9", DepType::Term), dep(1, "#8", DepType::Equality)];
+        let (nodes, edges) = build_graph(&deps);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(edges.len(), 1);
+        let edge = edges.first().unwrap();
+        assert_eq!(edge.dep_type, DepType::Term);
+        assert_eq!(edge.blamed, vec![String::from("#8"), String::from("#9")]);
     }
 
     #[test]
@@ -151,21 +352,40 @@ mod tests {
             quant: "#A".to_string()
         };
         let node2 = InstNode {
-            line_no: 2,
+            line_no: 3,
             quant: "ns#B".to_string()
         };
         let edge = InstEdge {
             node1: 1,
-            node2: 2
+            node2: 3,
+            dep_type: DepType::Term,
+            blamed: vec![String::from("#8")],
         };
-        let nodes = BTreeSet::from([node1, node2]);
-        let edges = BTreeSet::from([edge]);
-        assert_eq!(&get_dot("test", &nodes, &edges), 
-        r#"digraph test {
-	1 [ class="quant_A", tooltip="quant_#A" ]
-	2 [ class="quant_nsB", tooltip="quant_ns#B" ]
-	1 -> 2 [ ]
+        let graph = InstGraph { nodes: BTreeSet::from([node1, node2]), edges: BTreeSet::from([edge]), ..InstGraph::default() };
+        assert_eq!(&get_dot("test", &graph, &TestInfo, &NodeSize::Cost, None), 
+        r##"digraph test {
+	1 [ class="quant_A", tooltip="quant_#A", id="inst-1", URL="#inst-1" ]
+	3 [ class="quant_nsB", tooltip="quant_ns#B", id="inst-3", URL="#inst-3" ]
+	1 -> 3 [ style=solid, tooltip="f[8](a[1])" ]
 }
-"#);
+"##);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_inst_graph_endpoints() {
+        let dep = |from, to| Dependency { from, to, blamed: String::new(), dep_type: DepType::Term, quant: String::from("#A") };
+        // 5 is not in the graph (only its dependency on 1 is known), 4 is cut by the node limit
+        let deps = vec![dep(0, 1), dep(1, 2), dep(5, 2), dep(2, 3), dep(2, 4)];
+        let graph = InstGraph::build(&deps, 3, false);
+        assert_eq!(graph.nodes.iter().map(|node| node.line_no).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(graph.edges.iter().map(|edge| (edge.node1, edge.node2)).collect::<Vec<_>>(), vec![(1, 2), (2, 3)]);
+        assert!(graph.cut.is_empty());
+        let graph = InstGraph::build(&deps, 3, true);
+        assert_eq!(graph.cut.get(&2), Some(&CutNeighbours { parents: 1, children: 1 }));
+        let dot = get_dot("test", &graph, &TestInfo, &NodeSize::Cost, None);
+        assert!(dot.contains("\t\"cut-to-2\" -> 2 [ style=dotted ]\n"));
+        assert!(dot.contains("\t2 -> \"cut-from-2\" [ style=dotted ]\n"));
+        let empty = InstGraph::build(&vec![], 3, true);
+        assert_eq!(get_dot("test", &empty, &TestInfo, &NodeSize::Cost, None), "digraph test {\n}\n");
+    }
+}
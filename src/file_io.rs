@@ -1,7 +1,8 @@
+use std::collections::BTreeMap;
 use std::fs::{File, OpenOptions, self};
 use std::io::{self, BufRead, Write, BufWriter};
 use std::path::Path;
-use serde::Deserialize;
+use serde::{Serialize, Deserialize};
 
 use crate::items;
 
@@ -46,33 +47,238 @@ pub fn open_file_truncate(filename: &str) -> BufWriter<File> {
 }
 
 /// Parsing settings.
-#[derive(Default, Clone, Debug, Deserialize)]
+/// Settings missing from the settings file take their default values.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     // not all settings currently work
     /// Name of file to parse.
     pub file: String,
     /// Whether to consider terms with reused IDs as separate from previous terms with the same ID. Does not work yet.
     pub reuses: bool,
-    /// Print contents of parser to standard output item by item.
+    /// Log the items written to the text outputs one by one (at trace level, see `logging::Logger`).
     pub verbose: bool,
+    /// Level of the messages logged to standard error: `off`, `error`, `warn`, `info` (default), `debug` or `trace`.
+    pub log_level: String,
+    /// Levels for specific modules (and their submodules), overriding `log_level`, e.g. `{"render": "debug"}`.
+    pub log_modules: BTreeMap<String, String>,
+    /// Whether the command line shows a progress bar on standard error (only if it is a terminal).
+    #[serde(default = "default_true")]
+    pub progress_bar: bool,
     /// Whether to print all text/json files (Dot and SVG will always be generated regardless of this setting)
     pub save_all_data: bool,
+    /// Whether to also save all parsed results in the compact binary export format (see `binary_export`).
+    pub save_binary: bool,
+    /// Whether to also save all parsed results to an SQLite database (see `sqlite_export`).
+    pub save_sqlite: bool,
+    /// Whether to save a self-contained HTML report with the graph and the details of each instantiation.
+    #[serde(default = "default_true")]
+    pub html_report: bool,
+    /// Whether `output2.svg` embeds the quantifier colors in a `<style>` element instead of linking to `styles.css`.
+    pub embed_css: bool,
+    /// What the size of graph nodes shows.
+    pub node_size: NodeSize,
+    /// Whether to also output the graph of quantifiers (instances aggregated by quantifier) as Dot, SVG and JSON.
+    #[serde(default = "default_true")]
+    pub quant_graph: bool,
+    /// Line number of an instantiation to also output the subgraph around (0 for none).
+    pub subgraph_center: usize,
+    /// Include instantiations at most this many dependencies away from `subgraph_center` (0 for no limit).
+    pub subgraph_depth: usize,
+    /// Whether the subgraph includes the instantiations that led to `subgraph_center`, those it caused, or both.
+    pub subgraph_direction: SubgraphDirection,
+    /// If not empty, only instantiations of quantifiers matching one of these patterns are shown in graphs
+    /// (see `quant_filter::QuantFilter` for the pattern syntax).
+    pub include_quantifiers: Vec<String>,
+    /// Instantiations of quantifiers matching one of these patterns are left out of graphs,
+    /// e.g. `"arith#"` for arithmetic theory solving, `"MBQI"` or `"prelude_*"`.
+    #[serde(default = "default_exclude_quantifiers")]
+    pub exclude_quantifiers: Vec<String>,
+    /// Whether instantiations left out of graphs are also left out of the exported instantiations and dependencies.
+    pub filter_exports: bool,
+    /// Whether instantiations left out of the instantiation graph that depend on or are depended on by one in it
+    /// are shown as collapsed "…" placeholder nodes.
+    pub cut_neighbours: bool,
+    /// Colors for specific quantifiers, by quantifier ID or name (`*` and `?` can be used as wildcards),
+    /// e.g. `{"prelude_*": "#ffcc00"}`; other quantifiers get a color derived from their name.
+    pub quantifier_colors: BTreeMap<String, String>,
     /// Select a sort type (by line number, cost, depth, etc.)
     /// Does not work yet.
     /// ## TODO
     /// Replace with an enum
     pub sort_by: String,
-    /// Stop parsing after a certain amount of time. Does not work yet.
+    /// Stop parsing after this many seconds (0 for no limit); the results of the lines parsed so far are kept.
     pub timeout: f32,
     /// Parse only up to a certain number of lines. Does not work yet.
     pub line_limit: usize,
+    /// Keep reading the log file as Z3 appends to it, until `[eof]` is reached or parsing is stopped.
+    pub follow: bool,
+    /// In follow mode, seconds to wait before checking for new lines again.
+    pub poll_interval: f32,
+    /// In follow mode, seconds between refreshes of all output files (0 to only output once parsing stops).
+    pub refresh_interval: f32,
+    /// Save a snapshot of the parser state to this file after parsing (empty for none).
+    pub snapshot: String,
+    /// Restore the parser from this snapshot before parsing (empty for none).
+    /// Lines of the log that were already parsed are skipped.
+    pub resume_from: String,
+    /// SMT2 file the log was produced from, to show where its quantifiers and their triggers are written
+    /// (empty for none; set automatically when an `.smt2` file is run).
+    pub smt2_file: String,
+    /// Which solver wrote the log; `auto` recognizes cvc5 instantiation dumps by their first form and reads anything else as a Z3 log.
+    pub solver: Solver,
+    /// Solver run on `.smt2` files given instead of a log, with Z3's trace options (`z3` if empty).
+    pub solver_command: String,
+    /// Extra arguments for `solver_command`, e.g. `["-T:60", "smt.qi.eager_threshold=100"]`.
+    pub solver_args: Vec<String>,
+    /// Stop the solver with an error after a certain amount of time, in seconds (0 for no limit).
+    pub solver_timeout: f32,
+    /// How to render the graph as SVG.
+    pub renderer: Renderer,
+    /// Graphviz layout engine.
+    pub layout_engine: LayoutEngine,
+    /// Formats to render the graph in besides SVG (which is always rendered). Only SVG is supported without Graphviz.
+    pub output_formats: Vec<GraphFormat>,
+    /// Stop rendering with an error after a certain amount of time, in seconds (0 for no limit).
+    pub render_timeout: f32,
     // add settings for:
     // - number of instantiations to display in final visualization.
 }
 
+/// Theory-solving instantiations (`arith#`, `basic#`) are left out of graphs unless the settings file says otherwise.
+fn default_exclude_quantifiers() -> Vec<String> {
+    vec![String::from("arith#"), String::from("basic#")]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Solver whose log is parsed.
+#[derive(Default, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Solver {
+    /// Detected from the first line of the log.
+    #[default]
+    Auto,
+    /// A Z3 trace log (`trace=true`).
+    Z3,
+    /// The instantiations printed by cvc5 with `--dump-instantiations`.
+    Cvc5,
+}
+
+/// Choice of SVG renderer.
+#[derive(Default, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Renderer {
+    /// Graphviz if it is installed, otherwise the native renderer.
+    #[default]
+    Auto,
+    /// Graphviz's `dot` program.
+    Graphviz,
+    /// Built-in layered layout, no external programs needed.
+    Native,
+}
+
+/// Graphviz layout engine.
+#[derive(Default, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutEngine {
+    /// Hierarchical layout.
+    #[default]
+    Dot,
+    /// Force-directed layout for large graphs.
+    Sfdp,
+    /// Spring model layout.
+    Neato,
+}
+
+impl LayoutEngine {
+    /// Name of the engine as used on the Graphviz command line.
+    pub fn graphviz_name(&self) -> &'static str {
+        match self {
+            LayoutEngine::Dot => "dot",
+            LayoutEngine::Sfdp => "sfdp",
+            LayoutEngine::Neato => "neato",
+        }
+    }
+}
+
+/// Format to render the graph in.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    Svg,
+    Png,
+    Pdf,
+    /// Graphviz's plain text format with node positions.
+    Plain,
+}
+
+impl GraphFormat {
+    /// Name of the format as used on the Graphviz command line, also used as file extension.
+    pub fn graphviz_name(&self) -> &'static str {
+        match self {
+            GraphFormat::Svg => "svg",
+            GraphFormat::Png => "png",
+            GraphFormat::Pdf => "pdf",
+            GraphFormat::Plain => "plain",
+        }
+    }
+}
+
+/// What the size of instantiation graph nodes shows.
+#[derive(Default, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeSize {
+    /// Larger nodes for instantiations with a higher cost.
+    #[default]
+    Cost,
+    /// Larger nodes for instantiations of a later generation (`z3_gen`).
+    Generation,
+    /// All nodes have the same size.
+    Fixed,
+}
+
+/// Which dependencies to follow from the center of a subgraph.
+#[derive(Default, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubgraphDirection {
+    /// Instantiations that (transitively) led to the center.
+    Ancestors,
+    /// Instantiations that the center (transitively) caused.
+    Descendants,
+    /// Both ancestors and descendants.
+    #[default]
+    Both,
+}
 
 /// Read settings from `SETTINGS` json file, saving them to a `Settings` struct
 pub fn get_settings() -> Settings {
     let settings_text = fs::read_to_string(SETTINGS).expect("settings file should exist");
     serde_json::from_str(&settings_text).expect("Settings file should be valid JSON")
-}
\ No newline at end of file
+}
+/// Returns `settings` with the settings in `overrides` (in the format of the settings file) replaced.
+/// # Errors
+/// Errors if a setting in `overrides` has an invalid value.
+pub fn override_settings(settings: &Settings, overrides: serde_json::Map<String, serde_json::Value>) -> Result<Settings, String> {
+    let mut value = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    if let serde_json::Value::Object(map) = &mut value {
+        map.extend(overrides);
+    }
+    serde_json::from_value(value).map_err(|e| format!("Invalid settings: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_settings() {
+        let settings: Settings = serde_json::from_str("{\"file\": \"a.log\"}").unwrap();
+        assert_eq!(settings.file, "a.log");
+        assert_eq!(settings.exclude_quantifiers, vec!["arith#", "basic#"]);
+        assert!(settings.include_quantifiers.is_empty());
+        assert!(settings.progress_bar && settings.html_report && settings.quant_graph);
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+
+use crate::css::make_css::LegendEntry;
+use crate::items::{BlamedTermItem, EqualityExpl, Instantiation, Quantifier, Term, TwoDMap};
+use crate::smt2_source::SourceMap;
+
+/// subterms nested deeper than this are abbreviated in the report
+const MAX_TERM_DEPTH: usize = 8;
+
+/// Page layout: graph on the left, details of the selected instantiation on the right.
+const PAGE_CSS: &str = "body {
+\tmargin: 0;
+\tdisplay: flex;
+\theight: 100vh;
+\tfont-family: sans-serif;
+}
+#graph {
+\tflex: 3;
+\toverflow: auto;
+}
+#details {
+\tflex: 2;
+\toverflow: auto;
+\tpadding: 0 1em;
+\tborder-left: 1px solid #ccc;
+}
+#details li {
+\tfont-family: monospace;
+\tmargin-bottom: 0.3em;
+\tword-break: break-all;
+}
+.node {
+\tcursor: pointer;
+}
+.node.selected ellipse {
+\tstroke-width: 3px;
+}
+#legend {
+\tposition: fixed;
+\tleft: 0;
+\tbottom: 0;
+\tmax-height: 30vh;
+\toverflow: auto;
+\tmargin: 0;
+\tpadding: 0.5em 1em;
+\tlist-style: none;
+\tbackground: white;
+\tborder: 1px solid #ccc;
+}
+#legend span {
+\tdisplay: inline-block;
+\twidth: 1em;
+\theight: 1em;
+\tmargin-right: 0.5em;
+\tvertical-align: middle;
+\tborder: 1px solid black;
+\tborder-radius: 50%;
+}
+";
+
+/// Shows the details of an instantiation when its node is clicked or its anchor is opened.
+const PAGE_SCRIPT: &str = r##"const data = JSON.parse(document.getElementById("report-data").textContent);
+const details = document.getElementById("details");
+function list(title, items) {
+    const section = document.createElement("section");
+    const heading = document.createElement("h3");
+    heading.textContent = title + " (" + items.length + ")";
+    section.appendChild(heading);
+    const ul = document.createElement("ul");
+    for (const item of items) {
+        const li = document.createElement("li");
+        li.textContent = item;
+        ul.appendChild(li);
+    }
+    section.appendChild(ul);
+    return section;
+}
+function show(node, lineNo) {
+    const inst = data.instantiations[lineNo];
+    document.querySelectorAll(".node.selected").forEach(n => n.classList.remove("selected"));
+    node.classList.add("selected");
+    details.replaceChildren();
+    const heading = document.createElement("h2");
+    heading.textContent = "Instantiation @" + lineNo;
+    details.appendChild(heading);
+    if (!inst) {
+        details.appendChild(document.createTextNode("No data for this node."));
+        return;
+    }
+    const summary = document.createElement("p");
+    summary.textContent = "Quantifier " + inst.quantifier + ", cost " + inst.cost + ", generation " + inst.z3_gen
+        + (inst.depends_on.length ? ", depends on @" + inst.depends_on.join(", @") : "");
+    details.appendChild(summary);
+    if (inst.quantifier_text) {
+        details.appendChild(list("Quantifier", [inst.quantifier_text]));
+    }
+    if (inst.quantifier_source) {
+        details.appendChild(list("Source", inst.quantifier_source.split("\n")));
+    }
+    details.appendChild(list("Bound terms", inst.bound));
+    details.appendChild(list("Blamed terms", inst.blamed));
+    details.appendChild(list("Equalities", inst.equalities));
+    details.appendChild(list("Yielded terms", inst.yields));
+}
+const nodes = new Map();
+document.querySelectorAll("#graph .node").forEach(node => {
+    const title = node.querySelector("title");
+    // placeholder nodes for instantiations left out of the graph have no details
+    if (title && /^\d+$/.test(title.textContent.trim())) {
+        const lineNo = title.textContent.trim();
+        nodes.set(lineNo, node);
+        node.addEventListener("click", () => show(node, lineNo));
+    }
+});
+// nodes link to "#inst-<line number>", so that the details of an instantiation can be linked to
+function showAnchor() {
+    const match = location.hash.match(/^#inst-(\d+)$/);
+    if (match && nodes.has(match[1])) {
+        show(nodes.get(match[1]), match[1]);
+    }
+}
+window.addEventListener("hashchange", showAnchor);
+showAnchor();
+"##;
+
+/// Data shown in the report for one instantiation.
+#[derive(Debug, Serialize)]
+pub struct ReportInst {
+    pub quantifier: String,
+    pub quantifier_text: String,
+    /// where the quantifier is written in the SMT2 file and its triggers, empty if unknown
+    pub quantifier_source: String,
+    pub cost: f32,
+    pub z3_gen: u32,
+    pub bound: Vec<String>,
+    pub blamed: Vec<String>,
+    pub equalities: Vec<String>,
+    pub yields: Vec<String>,
+    pub depends_on: Vec<usize>,
+}
+
+/// Data embedded in the report, keyed by instantiation line number.
+#[derive(Debug, Default, Serialize)]
+pub struct ReportData {
+    pub instantiations: BTreeMap<usize, ReportInst>,
+}
+
+impl ReportData {
+    /// Adds the pretty-printed details of `inst` to the report, with the source of its quantifier if it is in `sources`.
+    pub fn add(&mut self, inst: &Instantiation, terms: &TwoDMap<Term>, quantifiers: &TwoDMap<Quantifier>, eq_expls: &BTreeMap<String, EqualityExpl>, sources: Option<&SourceMap>) {
+        let term_text = |id: &String| match terms.get(id) {
+            Some(term) => term.pretty_text_with_depth(terms, MAX_TERM_DEPTH),
+            None => id.clone(),
+        };
+        let quant = quantifiers.get(&inst.quant_id);
+        let blamed = inst.blamed_terms.iter().map(|item| match item {
+            BlamedTermItem::Single(t) => term_text(t),
+            BlamedTermItem::Pair(a, b) => format!("{} = {}", term_text(a), term_text(b)),
+        }).collect();
+        let equalities = inst.equality_expls.iter().map(|id| match eq_expls.get(id) {
+            Some(eq) => eq_expl_text(eq, &term_text),
+            None => id.clone(),
+        }).collect();
+        self.instantiations.insert(inst.line_no, ReportInst {
+            quantifier: quant.map_or_else(|| inst.quant_id.clone(), |q| q.name.clone()),
+            quantifier_text: quant.filter(|q| !q.term.is_empty() && terms.get(&q.term).is_some()).map_or_else(String::new, |q| q.pretty_text(terms)),
+            quantifier_source: quant.zip(sources).map_or_else(String::new, |(q, sources)| sources.describe(&q.name)),
+            cost: inst.cost,
+            z3_gen: inst.z3_gen,
+            bound: inst.bound_terms.iter().map(term_text).collect(),
+            blamed,
+            equalities,
+            yields: inst.yields_terms.iter().map(term_text).collect(),
+            depends_on: inst.dep_instantiations.clone(),
+        });
+    }
+}
+
+/// Describes an equality explanation with pretty-printed terms.
+fn eq_expl_text<F: Fn(&String) -> String>(eq: &EqualityExpl, term_text: &F) -> String {
+    use crate::items::EqualityExpl::*;
+    match eq {
+        Root { id } => format!("{} (root)", term_text(id)),
+        Literal { from, eq, to } => format!("{} = {} (literal {})", term_text(from), term_text(to), eq),
+        Congruence { from, arg_eqs, to } => {
+            let args: Vec<String> = arg_eqs.iter().map(|(a, b)| format!("{} = {}", a, b)).collect();
+            format!("{} = {} (congruence: {})", term_text(from), term_text(to), args.join(", "))
+        },
+        Theory { from, theory, to } => format!("{} = {} (theory {})", term_text(from), term_text(to), theory),
+        Axiom { from, to } => format!("{} = {} (axiom)", term_text(from), term_text(to)),
+        Unknown { from, to } => format!("{} = {} (unknown)", term_text(from), term_text(to)),
+    }
+}
+
+/// Escapes text for use in HTML.
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Builds a single self-contained HTML page with the rendered `svg`, the quantifier colors `css` (explained by `legend`)
+/// and the instantiation details `data`, which are shown when a node is clicked.
+pub fn make_report(title: &str, svg: &str, css: &str, legend: &[LegendEntry], data: &ReportData) -> String {
+    // the SVG is embedded inline, without its XML prolog
+    let svg = &svg[svg.find("<svg").unwrap_or(0)..];
+    let json = serde_json::to_string(data).unwrap_or_else(|err| panic!("{}", err))
+        .replace("</", "<\\/");
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html += &format!("<title>{}</title>\n", escape_html(title));
+    html += &format!("<style>\n{}{}</style>\n</head>\n<body>\n", PAGE_CSS, css);
+    html += &format!("<div id=\"graph\">\n{}</div>\n", svg);
+    if !legend.is_empty() {
+        html += "<ul id=\"legend\">\n";
+        for entry in legend {
+            html += &format!("<li title=\"{}\"><span style=\"background: {}\"></span>{}</li>\n",
+                escape_html(&entry.id).replace('"', "&quot;"), escape_html(&entry.color).replace('"', "&quot;"), escape_html(&entry.name));
+        }
+        html += "</ul>\n";
+    }
+    html += "<div id=\"details\"><h2>Click a node to show its details</h2></div>\n";
+    html += &format!("<script type=\"application/json\" id=\"report-data\">{}</script>\n", json);
+    html += &format!("<script>\n{}</script>\n</body>\n</html>\n", PAGE_SCRIPT);
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_io::Settings;
+    use crate::parsers::{test_logs::{self, parse}, Log};
+
+    #[test]
+    fn test_make_report() {
+        let svg = "<?xml version=\"1.0\"?>\n<svg><g class=\"node\"><title>10</title></g></svg>\n";
+        let mut data = ReportData::default();
+        data.instantiations.insert(10, ReportInst {
+            quantifier: String::from("q1"),
+            quantifier_text: String::new(),
+            quantifier_source: String::from("in.smt2:3: (forall ((x Int)) (f x))"),
+            cost: 2.0,
+            z3_gen: 1,
+            bound: vec![String::from("a[1]")],
+            blamed: vec![String::from("</script>")],
+            equalities: vec![],
+            yields: vec![],
+            depends_on: vec![],
+        });
+        let legend = [LegendEntry { id: String::from("#A"), name: String::from("q<1>"), color: String::from("#ffcc00") }];
+        let html = make_report("test <log>", svg, ".quant_A ellipse {}\n", &legend, &data);
+        assert!(html.contains("<title>test &lt;log&gt;</title>"));
+        assert!(html.contains("<div id=\"graph\">\n<svg>"));
+        assert!(!html.contains("<?xml"));
+        assert!(html.contains(".quant_A ellipse"));
+        assert!(html.contains("\"quantifier_source\":\"in.smt2:3: (forall"));
+        assert!(html.contains("<li title=\"#A\"><span style=\"background: #ffcc00\"></span>q&lt;1&gt;</li>"));
+        // embedded data cannot end the script element early
+        assert_eq!(html.matches("</script>").count(), 2);
+        assert!(!html.contains("href=") && !html.contains("src="));
+    }
+
+    #[test]
+    fn test_html_report() {
+        let parser = parse(Log::File(test_logs::SIMPLE.to_string()));
+        let settings = Settings { file: String::from("simple.log"), ..Settings::default() };
+        let svg = crate::render::native::render_dot(&parser.get_dot_output_as_string(&settings));
+        let html = parser.get_html_report(&svg, &settings);
+        assert!(html.contains("class=\"node quant_5\""));
+        assert!(html.contains(".quant_5 ellipse"));
+        let start = html.find("id=\"report-data\">").unwrap() + "id=\"report-data\">".len();
+        let end = start + html[start..].find("</script>").unwrap();
+        let data: serde_json::Value = serde_json::from_str(&html[start..end]).unwrap();
+        let inst = &data["instantiations"]["17"];
+        assert_eq!(inst["quantifier"], "q1");
+        assert_eq!(inst["bound"][0], "g[7](a[1])");
+        assert_eq!(inst["blamed"][0], "f[8](g[7](a[1]))");
+        assert_eq!(inst["yields"][0], "g[9](g[7](a[1]))");
+        assert_eq!(inst["depends_on"][0], 10);
+    }
+}
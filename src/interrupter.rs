@@ -23,6 +23,6 @@ impl ParseInterrupter {
             },
             Err(_poisoned) => {}    // don't need to do anything, parser panicked
         }
-        println!("Interrupted parsing");
+        log::info!("Interrupted parsing");
     }
 }
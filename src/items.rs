@@ -11,9 +11,9 @@ pub trait Print: Debug {
         format!("{:?}\n", self)
     }
 
-    /// Prints self to standard output.
-    fn print(&self) {
-        print!("{}", self.format());
+    /// Logs self at trace level.
+    fn trace(&self) {
+        log::trace!("{}", self.format().trim_end());
     }
 }
 
@@ -59,6 +59,25 @@ pub struct Term {
 
 impl Print for Term {}
 impl Term {
+    /// Like `pretty_text`, but subterms nested deeper than `max_depth` are abbreviated as `...`,
+    /// so that the text stays short for large terms.
+    pub fn pretty_text_with_depth(&self, map: &TwoDMap<Term>, max_depth: usize) -> String {
+        let name = String::from(&self.name) + "[" + &self.id.to_string() + "]";
+        if self.child_ids.is_empty() {
+            return name;
+        }
+        if max_depth == 0 {
+            return name + "(...)";
+        }
+        let child_text: Vec<String> = self.child_ids.iter().map(|c| {
+            match map.get(c) {
+                Some(term) => term.pretty_text_with_depth(map, max_depth - 1),
+                None => c.clone(),
+            }
+        }).collect();
+        name + "(" + &child_text.join(", ") + ")"
+    }
+
     pub fn pretty_text(&self, map: &TwoDMap<Term>) -> String {
         let child_text: Vec<String> = self.child_ids.iter().map(|c| {
             let term: &Term = map.get(c).unwrap();
@@ -176,11 +195,7 @@ pub fn parse_id(s: &str) -> (String, usize) {
     let split: Vec<&str> = s.split('#').collect(); 
     let n =
     if let Some(s2) = split.get(1) {
-        if let Ok(n) = s2.parse::<usize>() {
-            n
-        } else {
-            0
-        }
+        s2.parse::<usize>().unwrap_or_default()
     } else {
         0
     };
@@ -190,7 +205,7 @@ pub fn parse_id(s: &str) -> (String, usize) {
 
 /// A 2D map (Map of containing Maps). The `String` key is the namespace of a term/quantifier
 /// and the `usize` key is the term/quantifier's number.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TwoDMap<V>(pub HashMap<String, BTreeMap<usize, V>>);
 
 impl<T> Default for TwoDMap<T> {
@@ -245,7 +260,7 @@ pub type RcOption<T> = Option<Rc<RefCell<T>>>;
 /// - None: no dependency, because an instantiation is not dependent on any others.
 /// - Term: dependency based on a match without equalities.
 /// - Equality: dependency based on an equality.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum DepType {
     None,
     Term,
@@ -13,11 +13,53 @@ mod css;
 /// Generates Dot output from instantiation information.
 mod dot_output;
 
+/// Selection of instantiations by quantifier name or ID.
+pub mod quant_filter;
+
+/// Instantiation graph aggregated by quantifier.
+pub mod quant_graph;
+
+/// Extraction of the instantiations around a chosen instantiation.
+pub mod subgraph;
+
+/// Generates a self-contained interactive HTML report of the instantiation graph.
+mod html_report;
+
+/// Post-processing of rendered SVGs: style sheets and per-node data attributes.
+pub mod svg_post;
+
 /// Manages different sorting/filtering functionality (currently empty).
 mod sort_filter;
 
+/// S-expressions of SMT-LIB text, as in SMT2 files and solver output.
+pub mod sexpr;
+
 /// Parser structs and methods.
 pub mod parsers;
 
 /// Early stopping functionality for parsers.
 pub mod interrupter;
+
+/// Compact binary export of parsed results, and a loader for it.
+pub mod binary_export;
+
+/// Export of parsed results to an SQLite database for ad-hoc querying.
+pub mod sqlite_export;
+
+/// Leveled logging to standard error, with per-module levels.
+pub mod logging;
+
+/// Progress reports from parsers, and a terminal progress bar.
+pub mod progress;
+
+/// Parsing logs on a background worker, for the HTTP server.
+pub mod parse_service;
+
+/// Lookup and paging of parsed items by line number, term ID or quantifier ID.
+pub mod query;
+
+/// Running a solver on SMT2 files to produce trace logs.
+pub mod solver_runner;
+
+/// Locations of quantifiers in SMT2 input files.
+pub mod smt2_source;
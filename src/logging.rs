@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::str::FromStr;
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::file_io::Settings;
+
+/// Prefix of the log targets of this crate, left out of module names in filters and messages.
+const CRATE_PREFIX: &str = "prototype::";
+/// Module whose trace messages are the items dumped by `settings.verbose`.
+const ITEMS_MODULE: &str = "items";
+
+/// Logger writing messages to standard error, with a level per module.
+/// Without a logger (e.g. when using the crate as a library), nothing is logged.
+#[derive(Debug)]
+pub struct Logger {
+    default: LevelFilter,
+    /// levels by module path (without `prototype::`), most specific first
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Logger {
+    /// Logger for messages at `settings.log_level` or above, or the level in `settings.log_modules` for their module.
+    /// `settings.verbose` logs the items written to the text outputs.
+    /// # Errors
+    /// Errors if a level is not one of `off`, `error`, `warn`, `info`, `debug` and `trace`.
+    pub fn from_settings(settings: &Settings) -> Result<Logger, String> {
+        let mut modules = settings.log_modules.iter()
+            .map(|(module, level)| Ok((module.trim_start_matches(CRATE_PREFIX).to_string(), parse_level(level)?)))
+            .collect::<Result<BTreeMap<_, _>, String>>()?;
+        if settings.verbose {
+            modules.entry(ITEMS_MODULE.to_string()).or_insert(LevelFilter::Trace);
+        }
+        let mut modules: Vec<(String, LevelFilter)> = modules.into_iter().collect();
+        modules.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        Ok(Logger { default: parse_level(&settings.log_level)?, modules })
+    }
+
+    /// Level of the messages logged for `target` (a module path).
+    fn level(&self, target: &str) -> LevelFilter {
+        let module = target.trim_start_matches(CRATE_PREFIX);
+        self.modules.iter()
+            .find(|(prefix, _)| module == prefix || module.strip_prefix(prefix.as_str()).is_some_and(|rest| rest.starts_with("::")))
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// Installs this logger for the `log` macros.
+    /// # Errors
+    /// Errors if a logger has already been installed.
+    pub fn install(self) -> Result<(), String> {
+        let max_level = self.modules.iter().map(|(_, level)| *level).fold(self.default, std::cmp::max);
+        log::set_boxed_logger(Box::new(self)).map_err(|e| e.to_string())?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let module = record.target().trim_start_matches(CRATE_PREFIX);
+            let _ = writeln!(std::io::stderr().lock(), "[{} {}] {}", record.level(), module, record.args());
+        }
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// Level named `name` (case-insensitive); `info` if empty.
+fn parse_level(name: &str) -> Result<LevelFilter, String> {
+    if name.is_empty() {
+        return Ok(LevelFilter::Info);
+    }
+    LevelFilter::from_str(name).map_err(|_| format!("Invalid log level {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels() {
+        let settings = Settings {
+            log_level: String::from("warn"),
+            log_modules: BTreeMap::from([
+                (String::from("prototype::render"), String::from("debug")),
+                (String::from("parsers::z3parser1"), String::from("off")),
+            ]),
+            verbose: true,
+            ..Settings::default()
+        };
+        let logger = Logger::from_settings(&settings).unwrap();
+        assert_eq!(logger.level("prototype::render"), LevelFilter::Debug);
+        assert_eq!(logger.level("prototype::render::native"), LevelFilter::Debug);
+        assert_eq!(logger.level("prototype::renderer"), LevelFilter::Warn);
+        assert_eq!(logger.level("prototype::parsers::z3parser1"), LevelFilter::Off);
+        assert_eq!(logger.level("prototype::parsers"), LevelFilter::Warn);
+        assert_eq!(logger.level("prototype::items"), LevelFilter::Trace);
+        assert_eq!(Logger::from_settings(&Settings::default()).unwrap().level("actix_server"), LevelFilter::Info);
+        assert!(Logger::from_settings(&Settings { log_level: String::from("loud"), ..Settings::default() }).is_err());
+    }
+}
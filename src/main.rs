@@ -1,35 +1,134 @@
-use prototype::interrupter::ParseInterrupter;
 use prototype::file_io::*;
+use prototype::logging::Logger;
+use prototype::parse_service::{JobRegistry, JobState};
 use prototype::parsers::*;
+use prototype::progress::{ProgressBar, ProgressObserver};
+use prototype::solver_runner::{is_smt2_file, SolverRunner};
 use std::env;
+use std::io::{self, IsTerminal};
 use std::thread;
 use std::time::Duration;
 
+/// Filename used to read the log from standard input instead of a file.
+const STDIN_FILENAME: &str = "-";
+
+/// Usage: `prototype [LOG | SMT2 FILE] [--around LINE] [--depth N] [--direction ancestors|descendants|both] [--quiet]`.
+/// Command line arguments override the settings file.
+/// The log is parsed as a job of a `JobRegistry`, like those submitted to the server, which stops it after `timeout` seconds.
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let settings = get_settings();
-    let filename = if args.len() < 2 {
-        settings.file.to_string()
+    let mut settings = get_settings();
+    if let Err(e) = apply_args(&mut settings, &args[1..]).and_then(|_| Logger::from_settings(&settings)?.install()) {
+        eprintln!("{}", e);
+        return;
+    }
+    let filename = settings.file.to_string();
+    let solver = match settings.solver {
+        Solver::Auto if filename != STDIN_FILENAME && !is_smt2_file(&filename) => detect_solver(&filename),
+        Solver::Auto => Ok(Solver::Z3),
+        ref solver => Ok(solver.clone()),
+    };
+    match solver {
+        Ok(Solver::Cvc5) => {
+            if let Err(e) = run_cvc5(&filename, &settings) {
+                log::error!("{}", e);
+            }
+            return;
+        },
+        Ok(_) => {},
+        Err(e) => {
+            log::error!("{}", e);
+            return;
+        },
+    }
+    let parser = if settings.resume_from.is_empty() {
+        z3parser1::new()
+    } else {
+        match z3parser1::Z3Parser1::load_snapshot(&settings.resume_from) {
+            Ok(parser) => parser,
+            Err(e) => {
+                log::error!("{}", e);
+                return;
+            }
+        }
+    };
+    let progress_bar = (settings.progress_bar && io::stderr().is_terminal())
+        .then(|| Box::new(ProgressBar::new()) as Box<dyn ProgressObserver>);
+    let log = if filename == STDIN_FILENAME {
+        Log::Reader(Box::new(io::BufReader::new(io::stdin())))
     } else {
-        args[1].to_string()
+        Log::Filename(filename.clone())
     };
-    let mut parser = z3parser1::new();
-    let mut interrupt_agent = ParseInterrupter::new(&parser);
+    let registry = JobRegistry::with_runner(|parser, log, settings| run(parser, log, settings).map(|(svg,)| svg));
+    let id = registry.submit_log(&filename, parser, log, &settings, progress_bar);
     if settings.timeout > 0.0 {
-        let _timer = thread::spawn(move || {
-            thread::sleep(Duration::from_secs_f32(10.0));
-            interrupt_agent.stop_parsing();
-        });
-        let read_parse = thread::spawn(move || {
-            let settings = settings.clone();
-            if let Err(e) = parser.process_z3_file(&filename, &settings) {
-                println!("{}", e);
-            }
+        let registry = registry.clone();
+        let timeout = Duration::from_secs_f32(settings.timeout);
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            // fails if the job has already finished
+            let _ = registry.cancel(id);
         });
-        if let Err(e) = read_parse.join() {
-            println!("{:?}", e);
-        };
-    } else if let Err(e) = parser.process_z3_file(&filename, &settings) {
-        println!("{}", e);
+    }
+    if let Some(JobState::Failed { error }) = registry.wait(id).map(|info| info.state) {
+        log::error!("{}", error);
+    }
+}
+
+/// Applies command line arguments to `settings`: the log to parse, the subgraph to extract, and whether to only log errors.
+fn apply_args(settings: &mut Settings, args: &[String]) -> Result<(), String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+        let number = |value: &str| value.parse::<usize>().map_err(|_| format!("Invalid value for {}: {}", arg, value));
+        match arg.as_str() {
+            "--around" => settings.subgraph_center = number(value()?.trim_start_matches('@'))?,
+            "--depth" => settings.subgraph_depth = number(value()?)?,
+            "--direction" => {
+                settings.subgraph_direction = match value()?.as_str() {
+                    "ancestors" => SubgraphDirection::Ancestors,
+                    "descendants" => SubgraphDirection::Descendants,
+                    "both" => SubgraphDirection::Both,
+                    other => return Err(format!("Invalid value for {}: {}", arg, other)),
+                }
+            },
+            "--quiet" => settings.log_level = String::from("error"),
+            // the log given on the command line is recorded as the source of all outputs
+            _ if !arg.starts_with("--") => settings.file = arg.to_string(),
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    Ok(())
+}
+
+/// Parses `log`, or runs the solver on `settings.file` if it is an SMT2 file and parses its trace instead,
+/// then saves a snapshot of the parser if one was requested.
+fn run(parser: &mut z3parser1::Z3Parser1, log: Log, settings: &Settings) -> Result<(String,), String> {
+    let result = if is_smt2_file(&settings.file) {
+        let run = SolverRunner::from_settings(settings).run(&settings.file)?;
+        let settings = Settings { smt2_file: settings.file.clone(), ..settings.clone() };
+        parser.process_z3_file(&run.trace_file, &settings)?
+    } else if let Log::Filename(filename) = &log {
+        parser.process_z3_file(filename, settings)?
+    } else {
+        parser.process_z3_input(log, settings)?
+    };
+    if !settings.snapshot.is_empty() {
+        parser.save_snapshot(&settings.snapshot)?;
+    }
+    Ok(result)
+}
+
+/// Parses the cvc5 instantiation dump named `filename`, or standard input if it is `STDIN_FILENAME`.
+fn run_cvc5(filename: &str, settings: &Settings) -> Result<(String,), String> {
+    if is_smt2_file(filename) {
+        return Err(String::from("Only Z3 can be run on SMT2 files; run cvc5 with --dump-instantiations and parse its output"));
+    }
+    let mut parser = cvc5parser::Cvc5Parser::new();
+    if filename == STDIN_FILENAME {
+        let text = io::read_to_string(io::stdin()).map_err(|e| format!("Error reading standard input: {}", e))?;
+        parser.process_text(&text, settings)
+    } else {
+        parser.process_file(filename, settings)
     }
 }
@@ -0,0 +1,484 @@
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+use serde::Serialize;
+
+use crate::file_io::Settings;
+use crate::interrupter::ParseInterrupter;
+use crate::parsers::{Log, Z3LogParser};
+use crate::parsers::z3parser1::Z3Parser1;
+use crate::progress::{ProgressObserver, ProgressReport, ReportInterval};
+
+pub use crate::progress::Batch;
+
+/// Identifies a parse job.
+pub type JobId = u64;
+
+/// Parses a log, returning the rendered graph.
+pub type Runner = dyn Fn(&mut Z3Parser1, Log, &Settings) -> Result<String, String> + Send + Sync;
+
+/// How often a job's progress is updated and its new results are streamed while it is parsed.
+const JOB_REPORT_INTERVAL: ReportInterval = ReportInterval { lines: 10_000, bytes: 1 << 20 };
+
+/// How many finished jobs a registry keeps; the oldest are dropped, with their results, once there are more.
+pub const MAX_FINISHED_JOBS: usize = 32;
+
+/// Progress of a parse, updated by the parser while it runs (see `JobObserver`).
+#[derive(Debug, Default)]
+pub struct Progress {
+    lines_read: AtomicUsize,
+    bytes_read: AtomicU64,
+    instantiations: AtomicUsize,
+    /// receives batches of new results, if they are streamed
+    batches: Mutex<Option<mpsc::Sender<Batch>>>,
+}
+
+impl Progress {
+    /// Records the progress in `report`.
+    pub fn update(&self, report: &ProgressReport) {
+        self.lines_read.store(report.lines_read, Ordering::Relaxed);
+        self.bytes_read.store(report.bytes_read, Ordering::Relaxed);
+        self.instantiations.store(report.instantiations, Ordering::Relaxed);
+    }
+
+    /// Streams batches of the results found from now on to the returned receiver.
+    pub fn stream_batches(&self) -> mpsc::Receiver<Batch> {
+        let (sender, receiver) = mpsc::channel();
+        *self.batches.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// Whether batches of new results are streamed.
+    pub fn streams_batches(&self) -> bool {
+        self.batches.lock().unwrap().is_some()
+    }
+
+    /// Streams `batch`, if batches are streamed. Stops streaming once the receiver is dropped.
+    pub fn send_batch(&self, batch: Batch) {
+        let mut batches = self.batches.lock().unwrap();
+        if batches.as_ref().is_some_and(|sender| sender.send(batch).is_err()) {
+            *batches = None;
+        }
+    }
+
+    /// Stops streaming batches, disconnecting the receiver.
+    fn end_batches(&self) {
+        *self.batches.lock().unwrap() = None;
+    }
+
+    /// Progress so far, of a log `total_bytes` long (0 if unknown).
+    pub fn snapshot(&self, total_bytes: u64) -> ProgressInfo {
+        ProgressInfo {
+            lines_read: self.lines_read.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            total_bytes,
+            instantiations: self.instantiations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Progress of a parse at one point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ProgressInfo {
+    pub lines_read: usize,
+    /// bytes of the log parsed (line breaks counted as one byte)
+    pub bytes_read: u64,
+    /// size of the log in bytes, 0 if unknown
+    pub total_bytes: u64,
+    pub instantiations: usize,
+}
+
+/// Observer recording a job's progress and streaming its batches of new results.
+/// Progress reports are also passed on to `forward`, e.g. a progress bar.
+struct JobObserver {
+    progress: Arc<Progress>,
+    forward: Option<Box<dyn ProgressObserver>>,
+}
+
+impl ProgressObserver for JobObserver {
+    fn on_progress(&mut self, report: &ProgressReport) {
+        self.progress.update(report);
+        if let Some(forward) = &mut self.forward {
+            forward.on_progress(report);
+        }
+    }
+
+    fn streams_batches(&self) -> bool {
+        self.progress.streams_batches()
+    }
+
+    fn on_batch(&mut self, batch: Batch) {
+        self.progress.send_batch(batch);
+    }
+}
+
+/// State of a parse job.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum JobState {
+    /// waiting for the jobs submitted before it
+    Queued,
+    Running,
+    Done,
+    /// stopped before it was done; the results of the lines parsed so far are kept
+    Cancelled,
+    Failed { error: String },
+}
+
+impl JobState {
+    /// Whether the job will not change any more.
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, JobState::Queued | JobState::Running)
+    }
+}
+
+/// Public summary of a parse job.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: JobId,
+    /// name of the log, recorded as the source of the outputs
+    pub source: String,
+    #[serde(flatten)]
+    pub state: JobState,
+    pub progress: ProgressInfo,
+    /// whether results are available (also for jobs cancelled while running)
+    pub has_results: bool,
+}
+
+/// Results of a finished parse job.
+pub struct JobOutput {
+    /// the rendered instantiation graph
+    pub svg: String,
+    pub parser: Z3Parser1,
+}
+
+/// A submitted log and what became of it.
+struct Job {
+    id: JobId,
+    source: String,
+    total_bytes: u64,
+    state: Mutex<JobState>,
+    /// notified when `state` changes
+    changed: Condvar,
+    progress: Arc<Progress>,
+    interrupter: Mutex<ParseInterrupter>,
+    cancelled: AtomicBool,
+    /// taken by the worker when the job starts
+    input: Mutex<Option<(Z3Parser1, Log, Settings)>>,
+    output: Mutex<Option<Arc<JobOutput>>>,
+}
+
+impl Job {
+    fn info(&self) -> JobInfo {
+        JobInfo {
+            id: self.id,
+            source: self.source.clone(),
+            state: self.state.lock().unwrap().clone(),
+            progress: self.progress.snapshot(self.total_bytes),
+            has_results: self.output.lock().unwrap().is_some(),
+        }
+    }
+
+    fn set_state(&self, state: JobState) {
+        if state.is_finished() {
+            self.progress.end_batches();
+        }
+        *self.state.lock().unwrap() = state;
+        self.changed.notify_all();
+    }
+
+    /// Parses the log with `runner`, unless the job was cancelled before it started.
+    /// Returns the state the job finished in, which is left for the caller to set.
+    fn run(&self, runner: &Runner) -> Option<JobState> {
+        let (mut parser, log, settings) = self.input.lock().unwrap().take()?;
+        self.set_state(JobState::Running);
+        let state = match panic::catch_unwind(AssertUnwindSafe(|| runner(&mut parser, log, &settings))) {
+            Ok(Ok(svg)) => {
+                *self.output.lock().unwrap() = Some(Arc::new(JobOutput { svg, parser }));
+                if self.cancelled.load(Ordering::Relaxed) { JobState::Cancelled } else { JobState::Done }
+            },
+            Ok(Err(error)) => JobState::Failed { error },
+            Err(_) => JobState::Failed { error: String::from("Parser panicked") },
+        };
+        Some(state)
+    }
+}
+
+/// Parses submitted logs one at a time on a background thread, keeping track of their progress and results.
+/// Parsing writes its outputs to fixed file names, so logs must not be parsed concurrently.
+/// The results of the last `MAX_FINISHED_JOBS` finished jobs are kept until they are removed.
+#[derive(Clone)]
+pub struct JobRegistry {
+    jobs: Arc<Mutex<BTreeMap<JobId, Arc<Job>>>>,
+    next_id: Arc<AtomicU64>,
+    queue: mpsc::Sender<Arc<Job>>,
+}
+
+impl Default for JobRegistry {
+    fn default() -> JobRegistry {
+        JobRegistry::new()
+    }
+}
+
+impl JobRegistry {
+    /// Registry that parses logs, saves all outputs and renders the graph as configured in each job's settings.
+    pub fn new() -> JobRegistry {
+        JobRegistry::with_runner(|parser, log, settings| parser.process_z3_input(log, settings).map(|(svg,)| svg))
+    }
+
+    /// Registry that parses logs with `runner`.
+    /// A panic in `runner` fails that job; later jobs still run.
+    pub fn with_runner<F>(runner: F) -> JobRegistry
+    where F: Fn(&mut Z3Parser1, Log, &Settings) -> Result<String, String> + Send + Sync + 'static {
+        JobRegistry::keeping(runner, MAX_FINISHED_JOBS)
+    }
+
+    /// Registry that parses logs with `runner` and keeps at most `max_finished` finished jobs.
+    fn keeping<F>(runner: F, max_finished: usize) -> JobRegistry
+    where F: Fn(&mut Z3Parser1, Log, &Settings) -> Result<String, String> + Send + Sync + 'static {
+        let (queue, queued) = mpsc::channel::<Arc<Job>>();
+        let jobs: Arc<Mutex<BTreeMap<JobId, Arc<Job>>>> = Arc::default();
+        let registered = Arc::clone(&jobs);
+        thread::spawn(move || {
+            for job in queued {
+                let state = job.run(&runner);
+                // evict before anyone waiting for the job sees it finished
+                let mut jobs = registered.lock().unwrap();
+                if let Some(state) = state {
+                    job.set_state(state);
+                }
+                evict_finished(&mut jobs, max_finished);
+            }
+        });
+        JobRegistry { jobs, next_id: Arc::new(AtomicU64::new(1)), queue }
+    }
+
+    /// Queues the log `text` named `source` for parsing with `settings` (`settings.file` is set to `source`).
+    pub fn submit_text(&self, source: &str, text: String, settings: &Settings) -> JobId {
+        self.submit(source, Z3Parser1::default(), Log::File(text), settings, false, None).0
+    }
+
+    /// Queues the log `text` like `submit_text`, streaming the results to the returned receiver in batches
+    /// while it is parsed. The receiver is disconnected once the job has finished and all results have been sent.
+    pub fn submit_text_streaming(&self, source: &str, text: String, settings: &Settings) -> (JobId, mpsc::Receiver<Batch>) {
+        let (id, batches) = self.submit(source, Z3Parser1::default(), Log::File(text), settings, true, None);
+        (id, batches.expect("Batches are streamed"))
+    }
+
+    /// Queues the log file `filename` for parsing with `settings` (`settings.file` is set to `filename`).
+    /// # Errors
+    /// Errors if the file cannot be opened.
+    pub fn submit_file(&self, filename: &str, settings: &Settings) -> Result<JobId, String> {
+        std::fs::File::open(filename).map_err(|e| format!("Error opening {}: {}", filename, e))?;
+        Ok(self.submit(filename, Z3Parser1::default(), Log::Filename(filename.to_string()), settings, false, None).0)
+    }
+
+    /// Queues `log` named `source` for parsing with `parser`, e.g. one resumed from a snapshot, and `settings`
+    /// (`settings.file` is set to `source`). The job's progress reports are also passed on to `observer`, if any.
+    pub fn submit_log(&self, source: &str, parser: Z3Parser1, log: Log, settings: &Settings, observer: Option<Box<dyn ProgressObserver>>) -> JobId {
+        self.submit(source, parser, log, settings, false, observer).0
+    }
+
+    fn submit(&self, source: &str, mut parser: Z3Parser1, log: Log, settings: &Settings, stream: bool, forward: Option<Box<dyn ProgressObserver>>)
+    -> (JobId, Option<mpsc::Receiver<Batch>>) {
+        let total_bytes = match &log {
+            Log::Filename(filename) => std::fs::metadata(filename).map_or(0, |metadata| metadata.len()),
+            Log::File(text) => text.len() as u64,
+            Log::Reader(_) => 0,
+        };
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let progress = Arc::new(Progress::default());
+        let batches = stream.then(|| progress.stream_batches());
+        parser.observe_progress(Box::new(JobObserver { progress: Arc::clone(&progress), forward }), JOB_REPORT_INTERVAL);
+        let interrupter = ParseInterrupter::new(&parser);
+        let settings = Settings { file: source.to_string(), ..settings.clone() };
+        let job = Arc::new(Job {
+            id,
+            source: source.to_string(),
+            total_bytes,
+            state: Mutex::new(JobState::Queued),
+            changed: Condvar::new(),
+            progress,
+            interrupter: Mutex::new(interrupter),
+            cancelled: AtomicBool::new(false),
+            input: Mutex::new(Some((parser, log, settings))),
+            output: Mutex::new(None),
+        });
+        self.jobs.lock().unwrap().insert(id, Arc::clone(&job));
+        // the worker only stops once the registry is dropped
+        let _ = self.queue.send(job);
+        (id, batches)
+    }
+
+    fn job(&self, id: JobId) -> Option<Arc<Job>> {
+        self.jobs.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Summary of job `id`, if there is one.
+    pub fn get(&self, id: JobId) -> Option<JobInfo> {
+        self.job(id).map(|job| job.info())
+    }
+
+    /// Summaries of all jobs, in the order they were submitted.
+    pub fn list(&self) -> Vec<JobInfo> {
+        let jobs: Vec<Arc<Job>> = self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.iter().map(|job| job.info()).collect()
+    }
+
+    /// Results of job `id`, if it has any (yet).
+    pub fn output(&self, id: JobId) -> Option<Arc<JobOutput>> {
+        self.job(id)?.output.lock().unwrap().clone()
+    }
+
+    /// Blocks until job `id` is finished, returning its summary.
+    pub fn wait(&self, id: JobId) -> Option<JobInfo> {
+        let job = self.job(id)?;
+        let mut state = job.state.lock().unwrap();
+        while !state.is_finished() {
+            state = job.changed.wait(state).unwrap();
+        }
+        drop(state);
+        Some(job.info())
+    }
+
+    /// Stops job `id`: a queued job will not run, a running job stops parsing and keeps the results of the lines parsed so far.
+    /// # Errors
+    /// Errors if there is no such job or it has already finished.
+    pub fn cancel(&self, id: JobId) -> Result<JobInfo, String> {
+        let job = self.job(id).ok_or_else(|| format!("No job {}", id))?;
+        if job.state.lock().unwrap().is_finished() {
+            return Err(format!("Job {} has already finished", id));
+        }
+        job.cancelled.store(true, Ordering::Relaxed);
+        job.interrupter.lock().unwrap().stop_parsing();
+        if job.input.lock().unwrap().take().is_some() {
+            job.set_state(JobState::Cancelled);
+        }
+        Ok(job.info())
+    }
+
+    /// Drops finished job `id` and its results, returning its last summary.
+    /// # Errors
+    /// Errors if there is no such job or it has not finished yet (cancel it first).
+    pub fn remove(&self, id: JobId) -> Result<JobInfo, String> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get(&id).ok_or_else(|| format!("No job {}", id))?;
+        if !job.state.lock().unwrap().is_finished() {
+            return Err(format!("Job {} has not finished", id));
+        }
+        let job = jobs.remove(&id).unwrap();
+        drop(jobs);
+        Ok(job.info())
+    }
+}
+
+/// Drops the oldest finished jobs of `jobs` until at most `max_finished` are left.
+fn evict_finished(jobs: &mut BTreeMap<JobId, Arc<Job>>, max_finished: usize) {
+    let finished: Vec<JobId> = jobs.iter()
+        .filter(|(_, job)| job.state.lock().unwrap().is_finished())
+        .map(|(&id, _)| id)
+        .collect();
+    for id in finished.iter().take(finished.len().saturating_sub(max_finished)) {
+        jobs.remove(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{test_logs, LogParser};
+
+    /// Parses without saving or rendering; "renders" the number of lines parsed.
+    /// Logs starting with "block" wait until `release` is set.
+    fn test_registry(release: Arc<AtomicBool>) -> JobRegistry {
+        test_registry_keeping(release, MAX_FINISHED_JOBS)
+    }
+
+    fn test_registry_keeping(release: Arc<AtomicBool>, max_finished: usize) -> JobRegistry {
+        JobRegistry::keeping(move |parser, log, settings| {
+            if settings.file.starts_with("block") {
+                while !release.load(Ordering::Relaxed) {
+                    thread::yield_now();
+                }
+            }
+            if settings.file == "panic" {
+                panic!("test panic");
+            }
+            parser.main_parse_loop(log)?;
+            Ok(format!("{} lines", parser.lines_processed()))
+        }, max_finished)
+    }
+
+    #[test]
+    fn test_jobs() {
+        let release = Arc::new(AtomicBool::new(false));
+        let registry = test_registry(Arc::clone(&release));
+        let simple = registry.submit_text("simple.log", test_logs::SIMPLE.to_string(), &Settings::default());
+        let info = registry.wait(simple).unwrap();
+        assert_eq!(info.state, JobState::Done);
+        assert_eq!(info.progress.instantiations, 2);
+        // all but the `[eof]` line
+        assert_eq!(info.progress.bytes_read, (test_logs::SIMPLE.len() - "[eof]\n".len()) as u64);
+        assert_eq!(info.progress.total_bytes, test_logs::SIMPLE.len() as u64);
+        let output = registry.output(simple).unwrap();
+        assert_eq!(output.svg, format!("{} lines", info.progress.lines_read));
+        assert_eq!(output.parser.get_dependencies().len(), 2);
+
+        let panicked = registry.submit_text("panic", String::new(), &Settings::default());
+        assert_eq!(registry.wait(panicked).unwrap().state, JobState::Failed { error: String::from("Parser panicked") });
+
+        // the second job is cancelled while queued behind the first
+        let blocked = registry.submit_text("block.log", test_logs::SIMPLE.to_string(), &Settings::default());
+        let queued = registry.submit_text("queued.log", test_logs::SIMPLE.to_string(), &Settings::default());
+        assert_eq!(registry.cancel(queued).unwrap().state, JobState::Cancelled);
+        // the first is stopped before parsing any lines, but finishes with (empty) results
+        while registry.get(blocked).unwrap().state != JobState::Running {
+            thread::yield_now();
+        }
+        registry.cancel(blocked).unwrap();
+        release.store(true, Ordering::Relaxed);
+        let info = registry.wait(blocked).unwrap();
+        assert_eq!((info.state, info.has_results, info.progress.lines_read), (JobState::Cancelled, true, 0));
+        assert!(registry.output(queued).is_none());
+        assert!(registry.cancel(simple).is_err());
+        assert!(registry.get(100).is_none());
+        assert_eq!(registry.list().iter().map(|job| job.id).collect::<Vec<_>>(), vec![simple, panicked, blocked, queued]);
+    }
+
+    #[test]
+    fn test_eviction() {
+        let release = Arc::new(AtomicBool::new(false));
+        let registry = test_registry_keeping(Arc::clone(&release), 2);
+        let blocked = registry.submit_text("block.log", test_logs::SIMPLE.to_string(), &Settings::default());
+        let ids: Vec<JobId> = (0..3).map(|_| registry.submit_text("simple.log", test_logs::SIMPLE.to_string(), &Settings::default())).collect();
+        assert!(registry.remove(blocked).unwrap_err().contains("not finished"));
+        release.store(true, Ordering::Relaxed);
+        registry.wait(ids[2]).unwrap();
+        // the oldest two of the four finished jobs were dropped
+        assert_eq!(registry.list().iter().map(|job| job.id).collect::<Vec<_>>(), vec![ids[1], ids[2]]);
+        assert!(registry.output(blocked).is_none());
+        assert_eq!(registry.remove(ids[1]).unwrap().state, JobState::Done);
+        assert!(registry.remove(ids[1]).is_err());
+        assert_eq!(registry.list().len(), 1);
+    }
+
+    #[test]
+    fn test_streaming() {
+        let release = Arc::new(AtomicBool::new(false));
+        let registry = test_registry(Arc::clone(&release));
+        let (id, batches) = registry.submit_text_streaming("simple.log", test_logs::SIMPLE.to_string(), &Settings::default());
+        // the receiver is disconnected once the job is done
+        let batches: Vec<Batch> = batches.iter().collect();
+        assert_eq!(registry.get(id).unwrap().state, JobState::Done);
+        let lines: Vec<usize> = batches.iter().flat_map(|batch| batch.instantiations.iter().map(|inst| inst.line_no)).collect();
+        assert_eq!(lines, vec![10, 17]);
+        assert_eq!(batches.iter().map(|batch| batch.dependencies.len()).sum::<usize>(), 2);
+
+        let (id, batches) = registry.submit_text_streaming("block", test_logs::SIMPLE.to_string(), &Settings::default());
+        registry.cancel(id).unwrap();
+        release.store(true, Ordering::Relaxed);
+        assert!(batches.iter().all(|batch| batch.instantiations.is_empty()));
+    }
+}
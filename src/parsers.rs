@@ -1,23 +1,29 @@
 use std::error::Error;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::collections::{HashMap, BTreeMap, HashSet};
-use std::io::Write;
+use std::io::{self, BufRead, Write};
 use regex::Regex;
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
 
 use crate::file_io::*;
 use crate::items::*;
+use crate::progress::Phase;
 use crate::render::RenderSVG;
+use crate::svg_post::{add_stylesheet, NodeData, Stylesheet};
 
-/// Original Z3 log parser. Works with Z3 v.4.12.1, should work with other versions
-/// as long as the log format is the same for the important line cases.
-/// Compare with the log files in the `logs/` folder to see if this is the case.
+/// Original Z3 log parser. Works with logs of Z3 4.8.5 to 4.12 (see `LogFormat` for the layouts it tells apart
+/// by the `[tool-version]` line); logs of other versions are parsed with a warning.
+/// Compare with the log files in the `logs/` folder to see if their format is the same for the important line cases.
 /// 
 /// Tries to get around borrowing issues with indirection
 /// (saving ID strings to avoid managing actual references/pointers between terms/other items) and map lookups.
 pub mod z3parser1;
 
+/// Parser for the quantifier instantiations dumped by cvc5 (`--dump-instantiations`),
+/// producing the same items as `z3parser1`.
+pub mod cvc5parser;
+
 /// WORK IN PROGRESS, currently not functional.
 /// 
 /// A Z3 log parser using `Rc` and `RefCell` (interior mutability)
@@ -41,19 +47,106 @@ const OUT_PRETTY_QUANT: &str = "out/pretty-printed-quant.txt";
 const OUT_DOT: &str = "out/output.dot";
 const OUT_CSS: &str = "out/styles.css";
 const OUT_SVG_2: &str = "out/output2.svg";
+const OUT_REPORT: &str = "out/report.html";
 const OUT_SVG: &str = "out/output.svg";
+/// other graph formats are saved with this name and the format's extension
+const OUT_GRAPH: &str = "out/output";
+const OUT_QUANT_DOT: &str = "out/quantifiers.dot";
+const OUT_QUANT_SVG: &str = "out/quantifiers.svg";
+const OUT_SUBGRAPH_DOT: &str = "out/subgraph.dot";
+const OUT_SUBGRAPH_SVG: &str = "out/subgraph.svg";
 
 const OUT_INST_JSON: &str = "out/instantiations.json";
 const OUT_INST_SORTED_JSON: &str = "out/inst_sorted.json";
 const OUT_QUANT_JSON: &str = "out/quantifiers.json";
 const OUT_DEP_JSON: &str = "out/dependencies.json";
 const OUT_DEP_SORTED_JSON: &str = "out/deps_sorted.json";
+const OUT_QUANT_GRAPH_JSON: &str = "out/quant_graph.json";
+const OUT_SUBGRAPH_JSON: &str = "out/subgraph.json";
+const OUT_EXCLUDED_JSON: &str = "out/excluded.json";
 const OUT_TERMS_JSON: &str = "out/terms.json";
 const OUT_EQ_JSON: &str = "out/eq_expls.json";
 
+const OUT_BINARY: &str = "out/results.bin";
+const OUT_SQLITE: &str = "out/results.sqlite";
+
+/// poll interval (in seconds) used in follow mode if none is set
+const DEFAULT_POLL_INTERVAL: f32 = 0.5;
+
+/// Version of the JSON export schema (see the `schemas/` folder).
+/// Must be increased whenever the shape of an exported JSON file changes.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Identifies parser snapshot files
+const SNAPSHOT_MAGIC: &[u8; 8] = b"APSNAP\0\0";
+/// Snapshot format version; must be increased whenever the serialized parser state changes
+const SNAPSHOT_VERSION: u32 = 3;
+
+/// 64-bit FNV-1a parameters, used for `LogPrefix` hashes and quantifier colors (which must not change between versions)
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Continues the 64-bit FNV-1a hash `hash` (`FNV_OFFSET_BASIS` to start a new one) with `bytes`.
+pub(crate) fn fnv1a(hash: u64, bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(hash, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// Length and hash of the lines of a log parsed so far, saved in snapshots so that a restored parser can check
+/// that it resumes parsing the same log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogPrefix {
+    /// bytes of the lines, with line breaks counted as one byte
+    pub bytes: u64,
+    /// FNV-1a hash of the lines, each followed by `\n`
+    pub hash: u64,
+}
+
+impl Default for LogPrefix {
+    fn default() -> LogPrefix {
+        LogPrefix { bytes: 0, hash: FNV_OFFSET_BASIS }
+    }
+}
+
+impl LogPrefix {
+    /// Adds `line` (without its line break).
+    pub fn add_line(&mut self, line: &str) {
+        self.hash = fnv1a(self.hash, line.bytes().chain([b'\n']));
+        self.bytes += line.len() as u64 + 1;
+    }
+}
+
+/// A source of log lines for a parser.
 pub enum Log {
+    /// Path to a log file.
     Filename(String),
-    File(String)
+    /// Entire log contents.
+    File(String),
+    /// Any buffered reader, e.g. standard input when Z3 is run with `trace_file_name=/dev/stdout`.
+    Reader(Box<dyn BufRead + Send>),
+}
+
+/// The solver that wrote the log `filename`: cvc5 if its first non-blank line starts with the answer to the query
+/// (`sat`, `unsat` or `unknown`) or an `(instantiations` form, otherwise Z3.
+/// Logs that start with neither a cvc5 form nor a `[...]` trace line are read as Z3 logs with a warning.
+/// # Errors
+/// Errors if the file cannot be read.
+pub fn detect_solver(filename: &str) -> Result<Solver, String> {
+    let lines = read_lines(filename).map_err(|e| format!("Error opening {}: {}", filename, e))?;
+    for line in lines {
+        let line = line.map_err(|e| format!("Error reading {}: {}", filename, e))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if matches!(line.split_whitespace().next(), Some("sat" | "unsat" | "unknown")) || line.starts_with("(instantiations") {
+            return Ok(Solver::Cvc5);
+        }
+        if !line.starts_with('[') {
+            log::warn!("{} is neither a Z3 trace log nor a cvc5 instantiation dump, reading it as a Z3 log", filename);
+        }
+        return Ok(Solver::Z3);
+    }
+    Ok(Solver::Z3)
 }
 
 /// Trait for a generic SMT solver trace parser. Intended to support different solvers or log formats. 
@@ -119,38 +212,138 @@ pub trait Z3LogParser: LogParser {
     fn resolve_lit(&mut self, _l: &[&str]) {}
     fn conflict(&mut self, _l: &[&str]) {}
 
+    /// Number of log lines already processed. Parsing a log skips this many lines,
+    /// so that a parser restored from a snapshot resumes where it left off.
+    fn lines_processed(&self) -> usize {
+        0
+    }
+
+    /// Called after line `line_no` (0-based, without its line break) has been processed.
+    fn line_processed(&mut self, _line_no: usize, _line: &str) {}
+
+    /// Called for each line skipped because it was already processed (see `lines_processed`).
+    /// An error stops parsing, e.g. if the line differs from the one processed before.
+    fn line_skipped(&mut self, _line_no: usize, _line: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called with the size of the log in bytes before it is parsed, if it is known.
+    fn log_size(&mut self, _total_bytes: u64) {}
+
+    /// Called when a phase of processing the log starts.
+    fn phase_started(&mut self, _phase: Phase) {}
+
+    /// Called for a line of a line case the parser does not handle, e.g. `[new-case]`.
+    fn unknown_line_case(&mut self, _case: &str, line: &str) {
+        log::debug!("Unknown line case: {}", line);
+    }
+
+    /// Called once parsing stops without an error: at the end of the log, at `[eof]` or when the parser is interrupted.
+    fn parsing_finished(&mut self) {}
+
     /// Parses log data line by line.
-    fn main_parse_loop(&mut self, log: Log) {
+    /// # Errors
+    /// Errors if the log cannot be read or the lines already processed (see `lines_processed`) are not at its start.
+    fn main_parse_loop(&mut self, log: Log) -> Result<(), String> {
         match log {
             Log::Filename(filename) => {
-                if let Ok(lines) = read_lines(filename) {
-                    for (line_no, line) in lines.enumerate() {
-                        if !self.should_continue() {
-                            println!("Interrupted");
-                            break;
-                        }
-                        let l0 = line.unwrap_or_else(|_| panic!("Error reading line {}", line_no));
-                        match self.process_line(l0, line_no) {
-                            Ok(()) => continue,
-                            Err(_) => break,
-                        }
-                    }
-                } else {
-                    panic!("Failed reading lines")
+                if let Ok(metadata) = std::fs::metadata(&filename) {
+                    self.log_size(metadata.len());
                 }
+                let lines = read_lines(&filename).map_err(|e| format!("Error opening {}: {}", filename, e))?;
+                self.phase_started(Phase::Parsing);
+                self.parse_lines(lines)?;
             },
             Log::File(file) => {
-                for (line_no, line) in file.lines().enumerate() {
-                    match self.process_line(line.to_string(), line_no) {
-                        Ok(()) => continue,
-                        Err(_) => break,
-                    }
-                }
+                self.log_size(file.len() as u64);
+                self.phase_started(Phase::Parsing);
+                self.parse_lines(file.lines().map(Ok))?;
+            },
+            Log::Reader(reader) => {
+                self.phase_started(Phase::Parsing);
+                self.parse_lines(reader.lines())?;
             }
         } 
+        self.parsing_finished();
+        Ok(())
     } 
 
-    fn process_line(&mut self, line: String, line_no: usize) -> Result<(),Box<dyn Error>> {
+    /// Parses lines from an iterator until it is exhausted, `[eof]` is reached or the parser is interrupted.
+    /// # Errors
+    /// Errors if a line cannot be read (e.g. it is not valid UTF-8) or the lines already processed are not at the start of `lines`.
+    fn parse_lines<I, S>(&mut self, lines: I) -> Result<(), String> where I: Iterator<Item = io::Result<S>>, S: AsRef<str> {
+        let start = self.lines_processed();
+        let mut skipped = 0;
+        for (line_no, line) in lines.enumerate() {
+            if !self.should_continue() {
+                log::info!("Interrupted");
+                return Ok(());
+            }
+            let line = line.map_err(|e| format!("Error reading line {}: {}", line_no, e))?;
+            let line = line.as_ref();
+            if line_no < start {
+                self.line_skipped(line_no, line)?;
+                skipped += 1;
+            } else if self.process_line(line, line_no).is_ok() {
+                self.line_processed(line_no, line);
+            } else {
+                return Ok(());
+            }
+        }
+        if skipped < start {
+            return Err(format!("The log has only {} lines, but {} were already processed", skipped, start));
+        }
+        Ok(())
+    }
+
+    /// Parses a log that may still be growing (e.g. while Z3 is still writing to it).
+    /// When no complete line is available, waits for `poll_interval` before trying again;
+    /// `on_refresh` is called whenever `refresh_interval` has passed since the last call (never if it is zero).
+    /// Stops when `[eof]` is reached or the parser is interrupted.
+    /// # Errors
+    /// Errors if the log cannot be read or the lines already processed are not at its start.
+    fn follow_parse_loop<R, F>(&mut self, mut reader: R, poll_interval: Duration, refresh_interval: Duration, mut on_refresh: F) -> Result<(), String>
+    where R: BufRead, F: FnMut(&mut Self) {
+        let mut buf = String::new();
+        let start = self.lines_processed();
+        let mut line_no = 0;
+        let mut last_refresh = Instant::now();
+        self.phase_started(Phase::Parsing);
+        loop {
+            if !self.should_continue() {
+                log::info!("Interrupted");
+                break;
+            }
+            let read = reader.read_line(&mut buf).map_err(|e| format!("Error reading line {}: {}", line_no, e))?;
+            // a partially written line stays in `buf` until it is complete
+            if read > 0 && buf.ends_with('\n') {
+                let line = buf.trim_end_matches(['\n', '\r']);
+                if line_no < start {
+                    self.line_skipped(line_no, line)?;
+                } else if self.process_line(line, line_no).is_ok() {
+                    self.line_processed(line_no, line);
+                } else {
+                    break;
+                }
+                buf.clear();
+                line_no += 1;
+            }
+            // checked after every line too, so that outputs are refreshed even if the log grows faster than it is parsed
+            if !refresh_interval.is_zero() && last_refresh.elapsed() >= refresh_interval {
+                on_refresh(self);
+                self.phase_started(Phase::Parsing);
+                last_refresh = Instant::now();
+            }
+            if read == 0 {
+                // no new data yet
+                std::thread::sleep(poll_interval);
+            }
+        }
+        self.parsing_finished();
+        Ok(())
+    }
+
+    fn process_line(&mut self, line: &str, line_no: usize) -> Result<(),Box<dyn Error>> {
         let l: Vec<&str> = line.split(' ').collect();
         match l[0] {
             // match the line case
@@ -170,7 +363,7 @@ pub trait Z3LogParser: LogParser {
                 self.attach_meaning(&l);
             }
             "[attach-var-names]" => {
-                self.attach_vars(&l, &line);
+                self.attach_vars(&l, line);
             }
             "[attach-enode]" => {
                 self.attach_enode(&l);
@@ -182,7 +375,7 @@ pub trait Z3LogParser: LogParser {
                 self.new_match(&l, line_no);
             }
             "[inst-discovered]" => {
-                self.inst_discovered(&l, line_no, &line);
+                self.inst_discovered(&l, line_no, line);
             }
             "[instance]" => {
                 self.instance(&l, line_no);
@@ -223,44 +416,124 @@ pub trait Z3LogParser: LogParser {
             "[conflict]" => {
                 self.conflict(&l);
             }
-            _ => println!("Unknown line case: {}", line),
+            case => self.unknown_line_case(case, line),
         }
         Ok(())
     }
 
     /// Handles parsing, saves results to file, and outputs the final graph.
     /// Used to implement `LogParser::process_file` for a `Z3Parser`.
-fn process_z3_file(&mut self, filename: &str, settings: &Settings) -> Result<(String,), String> {
+    fn process_z3_file(&mut self, filename: &str, settings: &Settings) -> Result<(String,), String> {
+        if settings.follow {
+            return self.follow_z3_file(filename, settings);
+        }
+        self.process_z3_input(Log::Filename(filename.to_string()), settings)
+    }
+
+    /// Handles parsing of any `Log`, saves results to file, and outputs the final graph.
+    fn process_z3_input(&mut self, log: Log, settings: &Settings) -> Result<(String,), String> {
         let time = Instant::now();
+        let render_engine = crate::render::select_renderer(settings)?;
 
-        self.main_parse_loop(Log::Filename(filename.to_string()));
+        self.main_parse_loop(log)?;
 
         let elapsed_time = time.elapsed();
-        println!(
+        log::info!(
             "Finished parsing after {} seconds",
             elapsed_time.as_secs_f32()
         );
-        self.save_output_to_files(settings, &time);
-        let render_engine = crate::render::GraphVizRender;
-        let svg_result = render_engine.make_svg(OUT_DOT, OUT_SVG);
-        crate::render::add_link_to_svg(OUT_SVG, OUT_SVG_2);
-        println!(
-            "Finished render sequence after {} seconds",
-            time.elapsed().as_secs_f32()
-        );
+        let svg_result = self.save_and_render(settings, &time, render_engine.as_ref())?;
 
+        self.phase_started(Phase::Done);
         let elapsed_time = time.elapsed();
-        println!("Done, run took {} seconds.", elapsed_time.as_secs_f32());
+        log::info!("Done, run took {} seconds.", elapsed_time.as_secs_f32());
+
+        Ok((svg_result, ))
+    }
 
+    /// Follows a log file that Z3 is still appending to, refreshing all outputs every `settings.refresh_interval` seconds
+    /// so that a running verification can be watched live. Outputs are saved one final time once parsing stops.
+    fn follow_z3_file(&mut self, filename: &str, settings: &Settings) -> Result<(String,), String> {
+        let time = Instant::now();
+        let render_engine = crate::render::select_renderer(settings)?;
+        let file = std::fs::File::open(filename).map_err(|e| format!("Error opening {}: {}", filename, e))?;
+        let poll_interval = if settings.poll_interval > 0.0 { settings.poll_interval } else { DEFAULT_POLL_INTERVAL };
+        let poll_interval = Duration::from_secs_f32(poll_interval);
+        let refresh_interval = Duration::from_secs_f32(settings.refresh_interval.max(0.0));
+        self.follow_parse_loop(io::BufReader::new(file), poll_interval, refresh_interval, |parser| {
+            if let Err(e) = parser.save_and_render(settings, &time, render_engine.as_ref()) {
+                log::error!("{}", e);
+            }
+        })?;
+        log::info!(
+            "Finished parsing after {} seconds",
+            time.elapsed().as_secs_f32()
+        );
+        let svg_result = self.save_and_render(settings, &time, render_engine.as_ref())?;
+        self.phase_started(Phase::Done);
+        log::info!("Done, run took {} seconds.", time.elapsed().as_secs_f32());
         Ok((svg_result, ))
     }
 
+    /// Saves parser contents to files and renders the graph (in SVG and any other formats in `settings`), returning the SVG.
+    fn save_and_render(&mut self, settings: &Settings, time: &Instant, render_engine: &dyn RenderSVG) -> Result<String, String> {
+        self.save_output_to_files(settings, time);
+        self.phase_started(Phase::Rendering);
+        let svg_result = render_engine.make_svg(OUT_DOT, OUT_SVG)?;
+        let svg_result = crate::svg_post::add_node_data(&svg_result, &self.svg_node_data());
+        crate::render::write_output(OUT_SVG, svg_result.as_bytes())?;
+        let css;
+        let stylesheet = if settings.embed_css {
+            css = std::fs::read_to_string(OUT_CSS).map_err(|e| format!("Error reading {}: {}", OUT_CSS, e))?;
+            Stylesheet::Embed(&css)
+        } else {
+            Stylesheet::Link("styles.css")
+        };
+        crate::render::write_output(OUT_SVG_2, add_stylesheet(&svg_result, &stylesheet)?.as_bytes())?;
+        if settings.html_report {
+            self.save_html_report(OUT_REPORT, &svg_result, settings)?;
+        }
+        if settings.quant_graph {
+            let quant_svg = render_engine.make_svg(OUT_QUANT_DOT, OUT_QUANT_SVG)?;
+            crate::render::write_output(OUT_QUANT_SVG, add_stylesheet(&quant_svg, &stylesheet)?.as_bytes())?;
+        }
+        // the Dot file is only there if the subgraph could be extracted
+        if settings.subgraph_center != 0 && std::path::Path::new(OUT_SUBGRAPH_DOT).exists() {
+            let subgraph_svg = render_engine.make_svg(OUT_SUBGRAPH_DOT, OUT_SUBGRAPH_SVG)?;
+            let subgraph_svg = crate::svg_post::add_node_data(&subgraph_svg, &self.svg_node_data());
+            crate::render::write_output(OUT_SUBGRAPH_SVG, add_stylesheet(&subgraph_svg, &stylesheet)?.as_bytes())?;
+        }
+        for format in settings.output_formats.iter().filter(|format| **format != GraphFormat::Svg) {
+            let filename = format!("{}.{}", OUT_GRAPH, format.graphviz_name());
+            render_engine.make_format(OUT_DOT, &filename, format)?;
+        }
+        log::info!(
+            "Finished render sequence after {} seconds",
+            time.elapsed().as_secs_f32()
+        );
+        Ok(svg_result)
+    }
+
     fn process_z3_log(&mut self, log: String) {
-        self.main_parse_loop(Log::File(log));
+        if let Err(e) = self.main_parse_loop(Log::File(log)) {
+            log::error!("{}", e);
+        }
     }
     /// Save contents of parser to files.
     fn save_output_to_files(&mut self, settings: &Settings, time: &Instant);
 
+    /// Save a self-contained HTML report of the rendered graph `svg` to `filename`.
+    /// Does nothing unless implemented by the parser.
+    fn save_html_report(&self, _filename: &str, _svg: &str, _settings: &Settings) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// `data-*` attributes to add to each node of the rendered graph, keyed by node ID.
+    /// None unless implemented by the parser.
+    fn svg_node_data(&self) -> NodeData {
+        NodeData::new()
+    }
+
     // fn get_term(&self, id: &str) -> Term;
     // fn get_quantifiers(&self, id: &str) -> Quantifier;
     // fn get_instantiations(&self, line_no: &usize) -> Instantiation;
@@ -270,9 +543,260 @@ fn process_z3_file(&mut self, filename: &str, settings: &Settings) -> Result<(St
 /// Represented as a 16-digit hexadecimal number in log files.
 type Z3Fingerprint = u64;
 
+/// Parses a fingerprint. Z3 prints fingerprints as pointers, with a `0x` prefix on some platforms (e.g. Linux) but not others.
+/// # Panics
+/// Panics if `s` is not a hexadecimal number.
+pub fn parse_fingerprint(s: &str) -> Z3Fingerprint {
+    u64::from_str_radix(s.trim_start_matches("0x"), 16).expect("Should be valid hex string")
+}
+
+/// Oldest Z3 version whose logs are supported: the first to log blamed terms, equality explanations and `[inst-discovered]` lines.
+const MIN_Z3_VERSION: (u32, u32, u32) = (4, 8, 5);
+/// Newest Z3 release (major, minor) whose logs the parser was tested with (see `test_logs`).
+/// Logs of newer releases are read as `LogFormat::Current`, with a warning.
+const LATEST_TESTED_Z3_VERSION: (u32, u32) = (4, 12);
+
+/// Layout of Z3 log lines, which changed between Z3 versions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogFormat {
+    /// Z3 before 4.8.5 (unsupported): `[new-match]` lines end with the bound terms, without a `;` and the blamed terms,
+    /// so the bound terms are blamed instead. There are no equality explanations, so no equality dependencies.
+    Legacy,
+    /// Z3 4.8.5 and later: `[new-match] <fingerprint> <quantifier> <pattern> <bound terms> ; <blamed terms>`,
+    /// with `[inst-discovered]` lines for theory-solving and MBQI instantiations and `[mk-lambda]` lines for lambdas.
+    #[default]
+    Current,
+}
+
 /// Type of solver and version number
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct VersionInfo {
     solver: String,
     version: String,
-}
\ No newline at end of file
+}
+
+impl VersionInfo {
+    /// Major, minor and patch version, if the version starts with one (missing parts are 0).
+    fn version_number(&self) -> Option<(u32, u32, u32)> {
+        let mut parts = self.version.split('.').map(|part| {
+            let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse::<u32>().ok()
+        });
+        let major = parts.next()??;
+        Some((major, parts.next().flatten().unwrap_or(0), parts.next().flatten().unwrap_or(0)))
+    }
+
+    /// Layout of the lines of a log written by this solver version; `LogFormat::Current` if the version is unknown.
+    pub fn log_format(&self) -> LogFormat {
+        match self.version_number() {
+            Some(version) if version < MIN_Z3_VERSION => LogFormat::Legacy,
+            _ => LogFormat::Current,
+        }
+    }
+
+    /// Why logs of this solver version may not be parsed correctly, if they may not.
+    pub fn support_warning(&self) -> Option<String> {
+        let (min_major, min_minor, min_patch) = MIN_Z3_VERSION;
+        let (tested_major, tested_minor) = LATEST_TESTED_Z3_VERSION;
+        if self.solver.is_empty() {
+            return Some(format!("The log has no [tool-version] line, assuming the log format of Z3 {}.{}", tested_major, tested_minor));
+        }
+        if self.solver != "Z3" {
+            return Some(format!("Logs of {} are not supported, only those of Z3 (parsing it as a Z3 log)", self.solver));
+        }
+        match self.version_number() {
+            None => Some(format!("Unknown Z3 version {}, assuming the log format of Z3 {}.{}", self.version, tested_major, tested_minor)),
+            Some(version) if version < MIN_Z3_VERSION => Some(format!(
+                "Z3 {} is not supported, only Z3 {}.{}.{} and later: its logs have no blamed terms and equality explanations, \
+                so dependencies between instantiations are approximated from their bound terms",
+                self.version, min_major, min_minor, min_patch)),
+            Some((major, minor, _)) if (major, minor) > LATEST_TESTED_Z3_VERSION => Some(format!(
+                "Z3 {} is newer than the latest tested version ({}.{}); if parsing fails, compare its log format with the logs in logs/",
+                self.version, tested_major, tested_minor)),
+            Some(_) => None,
+        }
+    }
+}
+
+/// Metadata wrapped around the data of every exported JSON file.
+#[derive(Serialize)]
+pub struct ExportInfo<'a> {
+    pub schema_version: u32,
+    pub tool_version: &'static str,
+    /// Name of the parsed log (`-` for standard input)
+    pub source: &'a str,
+    pub solver: &'a VersionInfo,
+    pub settings: &'a Settings,
+}
+
+impl<'a> ExportInfo<'a> {
+    pub fn new(solver: &'a VersionInfo, settings: &'a Settings) -> ExportInfo<'a> {
+        ExportInfo {
+            schema_version: JSON_SCHEMA_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION"),
+            source: &settings.file,
+            solver,
+            settings,
+        }
+    }
+}
+
+/// An exported JSON file: the export metadata, followed by the exported data.
+#[derive(Serialize)]
+struct JsonEnvelope<'a, T: Serialize> {
+    #[serde(flatten)]
+    info: &'a ExportInfo<'a>,
+    data: T,
+}
+
+/// Returns `data` wrapped in an envelope with the export metadata `info`, as pretty-printed JSON.
+pub fn to_json_envelope<T: Serialize>(info: &ExportInfo, data: T) -> String {
+    serde_json::to_string_pretty(&JsonEnvelope { info, data }).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Small hand-written Z3 traces for unit tests.
+#[cfg(test)]
+pub(crate) mod test_logs {
+    use super::{Log, Z3LogParser};
+    use super::z3parser1::Z3Parser1;
+
+    /// Parses `log` with a new `Z3Parser1`.
+    pub fn parse(log: Log) -> Z3Parser1 {
+        let mut parser = Z3Parser1::default();
+        parser.main_parse_loop(log).unwrap();
+        parser
+    }
+
+    /// `SIMPLE` with a root and a literal equality explanation.
+    pub fn simple_with_eq_expls() -> Log {
+        Log::File(SIMPLE.replace("[eof]", "[eq-expl] #9 root\n[eq-expl] #6 lit #8 ; #8\n[eof]"))
+    }
+
+    /// Two instantiations of quantifier `#5` (`q1`), where the second is triggered by a term yielded by the first.
+    pub const SIMPLE: &str = "[tool-version] Z3 4.12.1
+[mk-app] #1 a
+[mk-var] #2 0
+[mk-app] #3 f #2
+[mk-app] #4 g #2
+[mk-quant] #5 q1 1 #3 #4
+[attach-var-names] #5 (|x| ; |Int|)
+[mk-app] #6 f #1
+[new-match] 1 #5 #3 #1 ; #6
+[instance] 1 #7 ; 1
+[mk-app] #7 g #1
+[mk-app] #8 f #7
+[attach-enode] #7 1
+[attach-enode] #8 1
+[end-of-instance]
+[new-match] 2 #5 #3 #7 ; #8
+[instance] 2 #9 ; 2
+[mk-app] #9 g #7
+[attach-enode] #9 2
+[end-of-instance]
+[eof]
+";
+
+    /// `SIMPLE` as logged by Z3 4.8.4, whose `[new-match]` lines have no blamed terms.
+    pub const Z3_4_8_4: &str = "[tool-version] Z3 4.8.4
+[mk-app] #1 a
+[mk-var] #2 0
+[mk-app] #3 f #2
+[mk-app] #4 g #2
+[mk-quant] #5 q1 1 #3 #4
+[mk-app] #6 f #1
+[new-match] 1 #5 #3 #1
+[instance] 1 #7 ; 1
+[mk-app] #7 g #1
+[mk-app] #8 f #7
+[attach-enode] #7 1
+[attach-enode] #8 1
+[end-of-instance]
+[new-match] 2 #5 #3 #7
+[instance] 2 #9 ; 2
+[mk-app] #9 g #7
+[attach-enode] #9 2
+[end-of-instance]
+[eof]
+";
+
+    /// `SIMPLE` as logged by Z3 4.8.17 on Linux (fingerprints with `0x`), with a lambda and a theory-solving instantiation
+    /// blaming a term yielded by the first instantiation.
+    pub const Z3_4_8_17: &str = "[tool-version] Z3 4.8.17
+[mk-app] #1 a
+[mk-var] #2 0
+[mk-app] #3 f #2
+[mk-app] #4 g #2
+[mk-quant] #5 q1 1 #3 #4
+[mk-lambda] #10 lambda 1 #4
+[attach-var-names] #5 (|x| ; |Int|)
+[mk-app] #6 f #1
+[new-match] 0x7f3a00000001 #5 #3 #1 ; #6
+[instance] 0x7f3a00000001 #7 ; 1
+[mk-app] #7 g #1
+[mk-app] #8 f #7
+[attach-enode] #7 1
+[attach-enode] #8 1
+[end-of-instance]
+[mk-app] #11 = #7 #1
+[inst-discovered] theory-solving 0x0 arith# ; #7
+[instance] 0x0 #11
+[end-of-instance]
+[new-match] 0x7f3a00000002 #5 #3 #7 ; #8
+[instance] 0x7f3a00000002 #9 ; 2
+[mk-app] #9 g #7
+[attach-enode] #9 2
+[end-of-instance]
+[eof]
+";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_logs::{parse, simple_with_eq_expls};
+
+    #[test]
+    fn test_json_exports_match_schemas() {
+        let mut parser = parse(simple_with_eq_expls());
+        parser.update_costs();
+        let settings = Settings { file: String::from("test.log"), ..Settings::default() };
+        let info = ExportInfo::new(parser.version_info(), &settings);
+        let insts_sorted = z3parser1::Z3Parser1::filter_instantiations_by_cost(parser.instantiations().values(), 250);
+        let exports = [
+            ("terms", to_json_envelope(&info, parser.terms().0.get("").unwrap())),
+            ("quantifiers", to_json_envelope(&info, parser.quantifiers().0.get("").unwrap())),
+            ("instantiations", to_json_envelope(&info, parser.instantiations())),
+            ("inst_sorted", to_json_envelope(&info, &insts_sorted)),
+            ("dependencies", to_json_envelope(&info, parser.get_dependencies())),
+            ("deps_sorted", to_json_envelope(&info, parser.get_dependencies())),
+            ("eq_expls", to_json_envelope(&info, parser.eq_expls())),
+            ("quant_graph", to_json_envelope(&info, parser.quant_graph(&HashSet::new()))),
+            ("subgraph", to_json_envelope(&info, parser.subgraph(17, &SubgraphDirection::Both, None).unwrap())),
+            ("excluded", to_json_envelope(&info, parser.excluded_counts(&HashSet::from([10, 17])))),
+        ];
+        let read_schema = |name: &str| -> serde_json::Value {
+            serde_json::from_str(&std::fs::read_to_string(format!("schemas/{}.schema.json", name)).unwrap()).unwrap()
+        };
+        // the shared envelope definitions must list every setting
+        let envelope = read_schema("envelope");
+        let settings_json = serde_json::to_value(&settings).unwrap();
+        let mut setting_names: Vec<&String> = settings_json.as_object().unwrap().keys().collect();
+        let mut defined_names: Vec<&String> = envelope["$defs"]["Settings"]["properties"].as_object().unwrap().keys().collect();
+        setting_names.sort();
+        defined_names.sort();
+        assert_eq!(setting_names, defined_names);
+        let envelope = jsonschema::Resource::from_contents(envelope).unwrap();
+        for (name, json) in exports {
+            let validator = jsonschema::options()
+                .with_resource("json-schema:///envelope.schema.json", envelope.clone())
+                .build(&read_schema(name))
+                .unwrap();
+            let instance: serde_json::Value = serde_json::from_str(&json).unwrap();
+            let errors: Vec<String> = validator.iter_errors(&instance).map(|e| e.to_string()).collect();
+            assert!(errors.is_empty(), "{}.json does not match its schema: {:?}", name, errors);
+            assert_eq!(instance["schema_version"], JSON_SCHEMA_VERSION);
+            assert_eq!(instance["solver"]["version"], "4.12.1");
+            assert_eq!(instance["source"], "test.log");
+        }
+    }
+}
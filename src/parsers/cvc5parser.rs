@@ -0,0 +1,360 @@
+use super::*;
+use super::z3parser1::Z3Parser1;
+use crate::sexpr::{read_sexprs, SExpr};
+
+/// What is needed of a quantifier to instantiate it.
+struct QuantBody {
+    vars: Vec<String>,
+    /// body without its `!` annotations, `None` if cvc5 only printed the quantifier's name
+    body: Option<SExpr>,
+    pattern_id: String,
+}
+
+/// Builds the items of the `Z3Parser1` model from the forms of an instantiation dump.
+#[derive(Default)]
+struct Builder {
+    terms: TwoDMap<Term>,
+    /// [term text => term ID], so that each distinct term is only created once
+    term_ids: HashMap<String, String>,
+    quantifiers: TwoDMap<Quantifier>,
+    /// [quantifier text => quantifier ID]
+    quant_ids: HashMap<String, String>,
+    bodies: HashMap<String, QuantBody>,
+    instantiations: BTreeMap<usize, Instantiation>,
+    dependencies: Vec<Dependency>,
+    /// [kind of form => number of forms skipped]
+    skipped: BTreeMap<String, usize>,
+}
+
+impl Builder {
+    /// Adds the items of one top-level form.
+    fn form(&mut self, expr: &SExpr) {
+        match expr {
+            // the answer to the query
+            SExpr::Atom(atom) if matches!(atom.as_str(), "sat" | "unsat" | "unknown") => {},
+            SExpr::List(items, _) if items.len() >= 2 && items[0].atom() == Some("instantiations") => {
+                let quant_id = self.quantifier(&items[1]);
+                for inst in &items[2..] {
+                    match inst {
+                        SExpr::List(bound, lines) => self.instantiation(&quant_id, bound, lines.first),
+                        SExpr::Atom(_) => self.skip("(instantiations atom"),
+                    }
+                }
+            },
+            SExpr::List(items, _) => self.skip(&format!("({}", items.first().map_or_else(String::new, SExpr::text))),
+            SExpr::Atom(atom) => self.skip(atom),
+        }
+    }
+
+    fn skip(&mut self, kind: &str) {
+        log::debug!("Skipping {}", kind);
+        *self.skipped.entry(kind.to_string()).or_default() += 1;
+    }
+
+    /// ID of the term for `expr`, creating it (and its subterms) if needed. IDs of created terms are added to `created`.
+    fn term(&mut self, expr: &SExpr, created: &mut Vec<String>) -> String {
+        let text = expr.text();
+        if let Some(id) = self.term_ids.get(&text) {
+            return id.clone();
+        }
+        let (name, child_ids) = match expr {
+            SExpr::Atom(atom) => (atom.clone(), vec![]),
+            SExpr::List(items, _) => match items.split_first() {
+                Some((SExpr::Atom(head), args)) => (head.clone(), args.iter().map(|arg| self.term(arg, created)).collect()),
+                // e.g. `((_ extract 7 0) x)` or a binder's variable list
+                _ => (String::new(), items.iter().map(|item| self.term(item, created)).collect()),
+            },
+        };
+        let num = self.term_ids.len() + 1;
+        let id = format!("#{}", num);
+        for child in &child_ids {
+            if let Some(child) = self.terms.get_mut(child) {
+                child.dep_term_ids.push(id.clone());
+            }
+        }
+        let term = Term {
+            kind: String::from("app"),
+            id: num,
+            name,
+            theory: String::new(),
+            child_ids,
+            dep_term_ids: vec![],
+            resp_inst_line_no: None,
+            text: text.clone(),
+        };
+        self.terms.insert(&id, term);
+        self.term_ids.insert(text, id.clone());
+        created.push(id.clone());
+        id
+    }
+
+    /// ID of the quantifier printed as `expr` (the quantified formula or its name), creating it if needed.
+    fn quantifier(&mut self, expr: &SExpr) -> String {
+        let text = expr.text();
+        if let Some(id) = self.quant_ids.get(&text) {
+            return id.clone();
+        }
+        let mut name = expr.atom().map(String::from);
+        let mut vars = vec![];
+        let mut body = None;
+        let mut term = String::from("N/A");
+        let mut pattern_id = String::new();
+        if let Some(quant) = expr.as_quantifier() {
+            vars = quant.vars;
+            name = quant.qid;
+            if let Some(pattern) = quant.patterns.first() {
+                pattern_id = self.term(pattern, &mut vec![]);
+            }
+            term = self.term(quant.body, &mut vec![]);
+            body = Some(quant.body.clone());
+        }
+        let id = self.term(expr, &mut vec![]);
+        let quant = Quantifier {
+            num_vars: vars.len(),
+            name: name.unwrap_or_else(|| id.clone()),
+            term,
+            cost: 0.0,
+            instances: vec![],
+            vars_set: !vars.is_empty(),
+            vars: vars.clone(),
+        };
+        self.quantifiers.insert(&id, quant);
+        self.quant_ids.insert(text, id.clone());
+        let vars = vars.into_iter().map(|(var, _)| var).collect();
+        self.bodies.insert(id.clone(), QuantBody { vars, body, pattern_id });
+        id
+    }
+
+    /// Adds the instantiation of quantifier `quant_id` with the terms `bound`, printed on line `line_no`.
+    /// It depends on the instantiations that created any of its bound terms.
+    fn instantiation(&mut self, quant_id: &str, bound: &[SExpr], line_no: usize) {
+        // several instantiations can be printed on one line, so they are numbered in the order they are printed
+        let inst_id = self.instantiations.len() + 1;
+        let bound_terms: Vec<String> = bound.iter().map(|term| self.term(term, &mut vec![])).collect();
+        let mut blamed_terms = vec![];
+        let mut dep_instantiations = vec![];
+        for id in &bound_terms {
+            blamed_terms.push(BlamedTermItem::Single(id.clone()));
+            if let Some(from) = self.terms.get(id).and_then(|term| term.resp_inst_line_no) {
+                if !dep_instantiations.contains(&from) {
+                    dep_instantiations.push(from);
+                    self.dependencies.push(Dependency {
+                        from,
+                        to: inst_id,
+                        blamed: id.clone(),
+                        dep_type: DepType::Term,
+                        quant: quant_id.to_string(),
+                    });
+                }
+            }
+        }
+        if dep_instantiations.is_empty() {
+            self.dependencies.push(Dependency {
+                from: 0,
+                to: inst_id,
+                blamed: String::new(),
+                dep_type: DepType::None,
+                quant: quant_id.to_string(),
+            });
+        }
+        let quant_body = &self.bodies[quant_id];
+        let pattern_id = quant_body.pattern_id.clone();
+        let instance = match &quant_body.body {
+            Some(body) if quant_body.vars.len() == bound.len() => {
+                let bindings = quant_body.vars.iter().map(String::as_str).zip(bound).collect();
+                Some(body.substitute(&bindings))
+            },
+            _ => None,
+        };
+        let mut yields_terms = vec![];
+        let resulting_term = match instance {
+            Some(instance) => self.term(&instance, &mut yields_terms),
+            None => String::from("N/A"),
+        };
+        for id in &yields_terms {
+            if let Some(term) = self.terms.get_mut(id) {
+                term.resp_inst_line_no = Some(inst_id);
+            }
+        }
+        if let Some(quant) = self.quantifiers.get_mut(quant_id) {
+            quant.instances.push(inst_id);
+        }
+        self.instantiations.insert(inst_id, Instantiation {
+            line_no: inst_id,
+            match_line_no: line_no,
+            fingerprint: 0,
+            resulting_term,
+            z3_gen: 0,
+            cost: 1.0,
+            quant_id: quant_id.to_string(),
+            pattern_id,
+            yields_terms,
+            bound_terms,
+            blamed_terms,
+            equality_expls: vec![],
+            dep_instantiations,
+        });
+    }
+}
+
+/// Parser for the quantifier instantiations cvc5 prints with `--dump-instantiations`, e.g.
+/// ```text
+/// unsat
+/// (instantiations (forall ((x Int)) (! (P x) :qid q1 :pattern ((P x))))
+///   ( 0 )
+///   ( (f 0) )
+/// )
+/// ```
+/// The instantiations are turned into the items of the Z3 parser, so that all graphs and exports work the same.
+/// Instantiations are numbered in the order they are printed, starting at 1, in place of the line numbers of Z3 logs;
+/// the line an instantiation is printed on is its `match_line_no`.
+///
+/// cvc5 does not log matches, so dependencies are approximate: an instantiation depends on the earlier ones
+/// whose instances contain one of its bound terms. Since cvc5 groups instantiations by quantifier rather than
+/// printing them in the order they happened, instantiations of a quantifier only depend on ones printed before them.
+pub struct Cvc5Parser {
+    results: Z3Parser1,
+    continue_parsing: Arc<Mutex<bool>>,
+}
+
+impl Cvc5Parser {
+    /// Items parsed so far.
+    pub fn results(&self) -> &Z3Parser1 {
+        &self.results
+    }
+
+    /// Parses the instantiation dump `text`, replacing any earlier results.
+    /// # Errors
+    /// Errors if `text` is not a sequence of s-expressions.
+    pub fn parse(&mut self, text: &str) -> Result<(), String> {
+        let forms = read_sexprs(text)?;
+        let mut builder = Builder::default();
+        for form in &forms {
+            if !self.should_continue() {
+                break;
+            }
+            builder.form(form);
+        }
+        for (kind, count) in &builder.skipped {
+            log::warn!("Skipped {} forms starting with {}", count, kind);
+        }
+        // the exports expect items without namespace
+        builder.terms.0.entry(String::new()).or_default();
+        builder.quantifiers.0.entry(String::new()).or_default();
+        let version_info = VersionInfo { solver: String::from("cvc5"), version: String::new() };
+        self.results = Z3Parser1::from_items(
+            builder.terms,
+            builder.quantifiers,
+            builder.instantiations,
+            builder.dependencies,
+            version_info,
+            text.lines().count(),
+        );
+        Ok(())
+    }
+
+    /// Parses the instantiation dump `text`, saves the results to files and renders the graph, returning the SVG.
+    pub fn process_text(&mut self, text: &str, settings: &Settings) -> Result<(String,), String> {
+        let time = Instant::now();
+        let render_engine = crate::render::select_renderer(settings)?;
+        self.parse(text)?;
+        log::info!("Finished parsing after {} seconds", time.elapsed().as_secs_f32());
+        let svg_result = self.results.save_and_render(settings, &time, render_engine.as_ref())?;
+        self.results.phase_started(Phase::Done);
+        log::info!("Done, run took {} seconds.", time.elapsed().as_secs_f32());
+        Ok((svg_result, ))
+    }
+}
+
+impl LogParser for Cvc5Parser {
+    fn process_file(&mut self, filename: &str, settings: &Settings) -> Result<(String,), String> {
+        let text = std::fs::read_to_string(filename).map_err(|e| format!("Error reading {}: {}", filename, e))?;
+        self.process_text(&text, settings)
+    }
+
+    fn new() -> Self {
+        Cvc5Parser { results: Z3Parser1::default(), continue_parsing: Arc::new(Mutex::new(true)) }
+    }
+
+    fn should_continue(&self) -> bool {
+        self.continue_parsing.lock().is_ok_and(|guard| *guard)
+    }
+
+    fn process_log(&mut self, log: String) {
+        if let Err(e) = self.parse(&log) {
+            log::error!("{}", e);
+        }
+    }
+
+    fn get_dependencies(&self) -> &Vec<Dependency> {
+        self.results.get_dependencies()
+    }
+}
+
+impl Interruptable for Cvc5Parser {
+    fn get_continue_mutex(&self) -> Arc<Mutex<bool>> {
+        Arc::clone(&self.continue_parsing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = "unsat
+(instantiations (forall ((x Int) (|y z| Int)) (! (= (f x) (g |y z|)) :qid ax_fg :pattern ((f x))))
+  ( 0 1 )
+  ( (g 1) 2 ) ; uses a term of the first instance
+)
+(skolem (exists ((x Int)) (P x)) ( k ))
+(instantiations (forall ((s String)) (P s))
+  ( \"a \"\"b\"\"\" )
+  ( (f 0) )
+)
+";
+
+    #[test]
+    fn test_dump() {
+        let mut parser = Cvc5Parser::new();
+        parser.parse(DUMP).unwrap();
+        let results = parser.results();
+        let quants: Vec<_> = results.quantifiers().0[""].values().map(|quant| (quant.name.as_str(), quant.num_vars)).collect();
+        assert_eq!(quants.len(), 2);
+        assert!(quants.contains(&("ax_fg", 2)));
+        let first = &results.instantiations()[&1];
+        let quant = results.quantifiers().get(&first.quant_id).unwrap();
+        assert_eq!(quant.vars, vec![(String::from("x"), String::from("Int")), (String::from("|y z|"), String::from("Int"))]);
+        assert_eq!(results.terms().get(&first.resulting_term).unwrap().text, "(= (f 0) (g 1))");
+        assert_eq!(results.terms().get(&first.pattern_id).unwrap().text, "((f x))");
+        let lines: Vec<_> = results.instantiations().values().map(|inst| (inst.line_no, inst.match_line_no)).collect();
+        assert_eq!(lines, vec![(1, 3), (2, 4), (3, 8), (4, 9)]);
+        assert_eq!(results.terms().get(&results.instantiations()[&3].bound_terms[0]).unwrap().name, "\"a \"\"b\"\"\"");
+        let deps: Vec<_> = parser.get_dependencies().iter().map(|dep| (dep.from, dep.to)).collect();
+        assert_eq!(deps, vec![(0, 1), (1, 2), (0, 3), (1, 4)]);
+        assert!(read_sexprs("(a (b)").is_err());
+
+        // instantiations printed on the same line are all kept
+        parser.parse("(instantiations q (a) (b))").unwrap();
+        let bound: Vec<_> = parser.results().instantiations().values().map(|inst| inst.bound_terms.clone()).collect();
+        assert_eq!(bound.len(), 2);
+        assert_ne!(bound[0], bound[1]);
+    }
+    #[test]
+    fn test_detect_solver() {
+        let path = std::env::temp_dir().join(format!("detect_solver_{}.log", std::process::id()));
+        let filename = path.to_str().unwrap();
+        let cases = [
+            (DUMP, Solver::Cvc5),
+            ("\n(instantiations q (a))\n", Solver::Cvc5),
+            (test_logs::SIMPLE, Solver::Z3),
+            // neither, e.g. a Git LFS pointer instead of the log
+            ("version https://git-lfs.github.com/spec/v1\n", Solver::Z3),
+            ("", Solver::Z3),
+        ];
+        for (text, solver) in cases {
+            std::fs::write(&path, text).unwrap();
+            assert_eq!(detect_solver(filename).unwrap(), solver, "{:?}", text);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
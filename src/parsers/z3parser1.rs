@@ -1,5 +1,14 @@
 use super::*;
+use crate::dot_output::{GraphInfo, NodeInfo};
+use crate::quant_filter::QuantFilter;
+use crate::progress::{Batch, Phase, ProgressObserver, ProgressTracker, ReportInterval};
+use crate::quant_graph::QuantGraph;
+use crate::smt2_source::SourceMap;
+use crate::subgraph::Subgraph;
+use std::borrow::Cow;
 
+/// Parser state is (de)serializable so that it can be saved to and restored from a snapshot.
+#[derive(Serialize, Deserialize)]
 pub struct Z3Parser1 {
     terms: TwoDMap<Term>,             // [namespace => [ID number => Term]]
     quantifiers: TwoDMap<Quantifier>, // [namespace => [ID number => Quantifier]]
@@ -11,8 +20,25 @@ pub struct Z3Parser1 {
     fingerprints: BTreeMap<usize, Z3Fingerprint>, // [match_line_number => fingerprint]
     dependencies: Vec<Dependency>,
     version_info: VersionInfo,
+    format: LogFormat, // layout of the log lines, from the Z3 version
+    lines_read: usize, // number of log lines processed so far
+    log_prefix: LogPrefix, // length and hash of the lines processed so far
+    #[serde(skip, default = "new_continue_mutex")]
     pub continue_parsing: Arc<Mutex<bool>>, // continue parsing or not?
+    #[serde(skip, default = "new_qvar_regexes")]
     qvar_re: Vec<Regex>,
+    #[serde(skip)]
+    skipped_prefix: LogPrefix, // length and hash of the lines skipped when resuming, compared to `log_prefix`
+    #[serde(skip)]
+    streamed: (usize, usize), // (line number of the last instantiation, number of dependencies) sent in batches
+    #[serde(skip)]
+    tracker: Option<ProgressTracker>, // reports progress and streams batches to an observer, if set
+    #[serde(skip)]
+    unknown_line_cases: BTreeMap<String, usize>, // [line case => number of lines skipped since parsing last finished]
+    #[serde(skip)]
+    sources: Option<SourceMap>, // quantifiers of `settings.smt2_file`, read when outputs are first saved
+    #[serde(skip)]
+    sources_file: String, // file `sources` was read from (also if reading it failed), so that it is read only once
 }
 
 pub fn new() -> Z3Parser1 {
@@ -58,6 +84,21 @@ impl Interruptable for Z3Parser1 {
     }
 }
 
+impl GraphInfo for Z3Parser1 {
+    fn node_info(&self, line_no: usize) -> Option<NodeInfo> {
+        let inst = self.instantiations.get(&line_no)?;
+        let quant_name = self.quantifiers.get(&inst.quant_id).map_or_else(|| inst.quant_id.clone(), |q| q.name.clone());
+        let source = self.sources.as_ref().map_or_else(String::new, |sources| sources.describe(&quant_name));
+        Some(NodeInfo { quant_name, source, cost: inst.cost, z3_gen: inst.z3_gen })
+    }
+
+    fn term_text(&self, id: &str) -> String {
+        // tooltips only show the top of large terms
+        const MAX_DEPTH: usize = 4;
+        self.terms.get(id).map_or_else(|| id.to_string(), |term| term.pretty_text_with_depth(&self.terms, MAX_DEPTH))
+    }
+}
+
 fn make_term(full_id: (String, usize), name: String, children: Vec<String>, text: String) -> Term {
     Term {
         kind: String::from("FORALL"),
@@ -77,10 +118,14 @@ impl Z3LogParser for Z3Parser1 {
             solver: l[1].to_string(),
             version: l[2].to_string(),
         };
-        println!(
+        log::info!(
             "{} {}",
             &self.version_info.solver, &self.version_info.version
         );
+        if let Some(warning) = self.version_info.support_warning() {
+            log::warn!("{}", warning);
+        }
+        self.format = self.version_info.log_format();
     }
 
     fn mk_quant(&mut self, l: &[&str]) {
@@ -185,7 +230,7 @@ impl Z3LogParser for Z3Parser1 {
         let t = self
             .terms
             .get_mut(l[1])
-            .expect(&(String::from("No term with ID ") + l[1]));
+            .unwrap_or_else(|| panic!("No term with ID {}", l[1]));
         let theory = l[2];
         t.theory = theory.to_string();
         let mut name = l[3].to_string();
@@ -201,7 +246,7 @@ impl Z3LogParser for Z3Parser1 {
         let q = self
             .quantifiers
             .get_mut(l[1])
-            .expect(&(String::from("No quantifier with ID") + l[1]));
+            .unwrap_or_else(|| panic!("No quantifier with ID {}", l[1]));
         let mut vars = vec![];
         for (i, caps) in qvar_re_0.captures_iter(l0).enumerate() {
             let sort = match caps.name("sort") {
@@ -285,25 +330,33 @@ impl Z3LogParser for Z3Parser1 {
     }
 
     fn new_match(&mut self, l: &[&str], line_no: usize) {
-        let semicolon_index = l
-            .iter()
-            .position(|&t| t == ";")
-            .expect("Semicolon should be found");
+        let semicolon_index = match self.format {
+            LogFormat::Legacy => l.len(),
+            LogFormat::Current => l
+                .iter()
+                .position(|&t| t == ";")
+                .expect("Semicolon should be found"),
+        };
         let bound_terms: Vec<String> = l[4..semicolon_index]
             .iter()
             .map(|&t| String::from(t))
             .collect();
+        // logs of old Z3 versions do not say which terms were matched, so the bound terms are blamed
+        let blamed_words = match self.format {
+            LogFormat::Legacy => &l[4..],
+            LogFormat::Current => &l[semicolon_index + 1..],
+        };
         let mut blamed_terms: Vec<BlamedTermItem> = vec![];
-        let fingerprint = u64::from_str_radix(l[1], 16).expect("Should be valid hex string");
+        let fingerprint = parse_fingerprint(l[1]);
         let quant_id = l[2];
         let pattern_id = l[3];
         let mut equality_expls = vec![];
         let mut dep_instantiations = vec![];
         self.temp_dependencies.insert(line_no + 1, vec![]);
-        for (i, word) in l[semicolon_index + 1..].iter().enumerate() {
+        for (i, word) in blamed_words.iter().enumerate() {
             if let Some(first_term) = word.strip_prefix('(') {
                 // assumes that if we see "(#A", the next word in the split is "#B)"
-                let next_word = l[semicolon_index + i + 2];
+                let next_word = blamed_words[i + 1];
                 let second_term = next_word.strip_suffix(')').unwrap();
                 if first_term != second_term {
                     let eq = self.eq_expls.get(first_term).unwrap();
@@ -372,7 +425,7 @@ impl Z3LogParser for Z3Parser1 {
 
     fn inst_discovered(&mut self, l: &[&str], line_no: usize, l0: &str) {
         let method = l[1];
-        let fingerprint = u64::from_str_radix(l[2], 16).expect("Should be valid hex string");
+        let fingerprint = parse_fingerprint(l[2]);
         let mut blamed_terms = vec![];
         let mut bound_terms = vec![];
         let mut dep_instantiations = vec![];
@@ -406,7 +459,7 @@ impl Z3LogParser for Z3Parser1 {
             name = String::from("MBQI");
             bound_terms = l[3..].iter().map(|t| t.to_string()).collect();
         } else {
-            println!("Unknown line case: {}", l0);
+            self.unknown_line_case(&format!("{} {}", l[0], method), l0);
         }
         if !self.quantifiers.0.contains_key(&name) {
             let q = Quantifier {
@@ -446,7 +499,7 @@ impl Z3LogParser for Z3Parser1 {
     }
 
     fn instance(&mut self, l: &[&str], line_no: usize) {
-        let fingerprint = u64::from_str_radix(l[1], 16).expect("Should be valid hex string");
+        let fingerprint = parse_fingerprint(l[1]);
         self.fingerprints.insert(line_no + 1, fingerprint);
         let instant = self
             .matches
@@ -466,6 +519,68 @@ impl Z3LogParser for Z3Parser1 {
         self.instantiations.insert(line_no + 1, instant.clone());
     }
 
+    fn svg_node_data(&self) -> NodeData {
+        let mut data = NodeData::new();
+        for dep in &self.dependencies {
+            let Some(inst) = self.instantiations.get(&dep.to) else { continue };
+            let quant_name = self.quantifiers.get(&inst.quant_id).map_or(inst.quant_id.as_str(), |q| q.name.as_str());
+            data.entry(inst.line_no.to_string()).or_insert_with(|| vec![
+                (String::from("inst-line"), inst.line_no.to_string()),
+                (String::from("quant-id"), inst.quant_id.clone()),
+                (String::from("quantifier"), quant_name.to_string()),
+                (String::from("cost"), inst.cost.to_string()),
+            ]);
+        }
+        data
+    }
+
+    fn save_html_report(&self, filename: &str, svg: &str, settings: &Settings) -> Result<(), String> {
+        let html = self.get_html_report(svg, settings);
+        let mut file = open_file_truncate(filename);
+        file.write_all(html.as_bytes()).and_then(|_| file.flush()).map_err(|e| format!("Error writing {}: {}", filename, e))
+    }
+
+    fn lines_processed(&self) -> usize {
+        self.lines_read
+    }
+
+    fn line_processed(&mut self, line_no: usize, line: &str) {
+        self.lines_read = line_no + 1;
+        self.log_prefix.add_line(line);
+        if self.tracker.as_mut().is_some_and(|tracker| tracker.line_processed(line.len())) {
+            self.report_now();
+            // instantiations are only complete at their [end-of-instance]
+            let open = self.inst_stack.first().map_or(usize::MAX, |(line_no, _)| *line_no);
+            self.send_batch_until(open);
+        }
+    }
+
+    fn line_skipped(&mut self, line_no: usize, line: &str) -> Result<(), String> {
+        self.skipped_prefix.add_line(line);
+        if line_no + 1 == self.lines_read && self.skipped_prefix != self.log_prefix {
+            return Err(format!("The first {} lines of the log differ from those parsed before the snapshot was saved", self.lines_read));
+        }
+        Ok(())
+    }
+
+    fn log_size(&mut self, total_bytes: u64) {
+        if let Some(tracker) = &mut self.tracker {
+            tracker.set_total_bytes(total_bytes);
+        }
+    }
+
+    fn phase_started(&mut self, phase: Phase) {
+        let (instantiations, terms) = (self.instantiations.len(), self.term_count());
+        if let Some(tracker) = &mut self.tracker {
+            tracker.start_phase(phase, self.lines_read, instantiations, terms);
+        }
+    }
+
+    fn unknown_line_case(&mut self, case: &str, line: &str) {
+        log::debug!("Unknown line case: {}", line);
+        *self.unknown_line_cases.entry(case.to_string()).or_default() += 1;
+    }
+
     fn end_of_instance(&mut self) {
         let (inst_line_no, _fingerprint) = self.inst_stack.pop().unwrap();
         let inst = self.instantiations.get_mut(&inst_line_no).unwrap();
@@ -477,39 +592,72 @@ impl Z3LogParser for Z3Parser1 {
         self.dependencies.append(deps);
     }
 
+    fn parsing_finished(&mut self) {
+        for (case, count) in std::mem::take(&mut self.unknown_line_cases) {
+            log::warn!("Skipped {} lines of unknown line case {}", count, case);
+        }
+        // the warning for a known version is logged with its `[tool-version]` line
+        if self.version_info.solver.is_empty() && self.lines_read > 0 {
+            if let Some(warning) = self.version_info.support_warning() {
+                log::warn!("{}", warning);
+            }
+        }
+        self.report_now();
+        self.send_batch_until(usize::MAX);
+    }
+
     fn save_output_to_files(&mut self, settings: &Settings, now: &Instant) {
+        self.load_sources(settings);
+        // update quantifier, instantiation costs
+        self.phase_started(Phase::Costing);
+        self.update_costs();
+        log::info!(
+            "Finished cost after {} seconds",
+            now.elapsed().as_secs_f32()
+        );
+        self.phase_started(Phase::Exporting);
+        let info = ExportInfo::new(&self.version_info, settings);
+
         // save terms
         let terms_main = self.terms.0.get("").unwrap();
         save_terms_to_txt(settings, terms_main);
-        save_to_json(OUT_TERMS_JSON, terms_main);
-        println!(
+        save_to_json(OUT_TERMS_JSON, &info, terms_main);
+        log::info!(
             "Finished printing terms ({}) after {} seconds",
             terms_main.len(),
             now.elapsed().as_secs_f32()
         );
 
-        // update quantifier, instantiation costs
-        self.update_costs();
-        println!(
-            "Finished cost after {} seconds",
-            now.elapsed().as_secs_f32()
-        );
+        // apply the quantifier filter
+        let filter = QuantFilter::from_settings(settings);
+        let excluded = self.excluded_instantiations(&filter);
+        let excluded_counts = self.excluded_counts(&excluded);
+        for (quant_id, count) in &excluded_counts {
+            log::info!("Excluded {} instantiations of {}", count, quant_id);
+        }
+        save_to_json(OUT_EXCLUDED_JSON, &info, &excluded_counts);
+        let (instantiations, dependencies) = if settings.filter_exports {
+            let (instantiations, dependencies) = self.without_excluded(&excluded);
+            (Cow::Owned(instantiations), Cow::Owned(dependencies))
+        } else {
+            (Cow::Borrowed(&self.instantiations), Cow::Borrowed(&self.dependencies))
+        };
 
         // save instantiations
-        Z3Parser1::save_to_file(OUT_INST, &self.instantiations, |_| (), settings);
-        save_to_json(OUT_INST_JSON, &self.instantiations);
-        println!(
+        Z3Parser1::save_to_file(OUT_INST, &instantiations, |_| (), settings);
+        save_to_json(OUT_INST_JSON, &info, &instantiations);
+        log::info!(
             "Finished printing instantiations ({}) after {} seconds",
-            &self.instantiations.len(),
+            &instantiations.len(),
             now.elapsed().as_secs_f32()
         );
 
-        let insts_sorted = Z3Parser1::filter_instantiations_by_cost(&self.instantiations, 250);
+        let insts_sorted = Z3Parser1::filter_instantiations_by_cost(instantiations.values(), 250);
         // sorted by cost
         // TODO: make constants
         Z3Parser1::save_to_file_vec(OUT_INST_SORTED, &insts_sorted, |_| (), settings);
-        save_to_json_vec(OUT_INST_SORTED_JSON, &insts_sorted);
-        println!(
+        save_to_json_vec(OUT_INST_SORTED_JSON, &info, &insts_sorted);
+        log::info!(
             "Finished printing cost-sorted instantiations ({}) after {} seconds",
             &self.instantiations.len(),
             now.elapsed().as_secs_f32()
@@ -518,27 +666,27 @@ impl Z3LogParser for Z3Parser1 {
         // save quantifiers
         let quantifiers_main = self.quantifiers.0.get("").unwrap();
         self.save_quants_to_txt(settings, quantifiers_main);
-        save_to_json(OUT_QUANT_JSON, quantifiers_main);
-        println!(
+        save_to_json(OUT_QUANT_JSON, &info, quantifiers_main);
+        log::info!(
             "Finished printing quants ({}) after {} seconds",
             quantifiers_main.len(),
             now.elapsed().as_secs_f32()
         );
 
         // save dependencies
-        Z3Parser1::save_to_file_vec(OUT_DEP, &self.dependencies, |_| (), settings);
-        save_to_json_vec(OUT_DEP_JSON, &self.dependencies);
-        println!(
+        Z3Parser1::save_to_file_vec(OUT_DEP, &dependencies, |_| (), settings);
+        save_to_json_vec(OUT_DEP_JSON, &info, &dependencies);
+        log::info!(
             "Finished printing deps ({}) after {} seconds",
-            self.dependencies.len(),
+            dependencies.len(),
             now.elapsed().as_secs_f32()
         );
 
         // sort dependencies, save sorted
-        let sorted_deps = Self::filter_dependencies_by_cost(&insts_sorted, &self.dependencies);
+        let sorted_deps = Self::filter_dependencies_by_cost(&insts_sorted, &dependencies);
         Z3Parser1::save_to_file_vec(OUT_DEP_SORTED, &sorted_deps, |_| (), settings);
-        save_to_json_vec(OUT_DEP_SORTED_JSON, &self.dependencies);
-        println!(
+        save_to_json_vec(OUT_DEP_SORTED_JSON, &info, &self.dependencies);
+        log::info!(
             "Finished printing cost-sorted deps ({}) after {} seconds",
             sorted_deps.len(),
             now.elapsed().as_secs_f32()
@@ -546,35 +694,108 @@ impl Z3LogParser for Z3Parser1 {
 
         // save equality explanations
         Z3Parser1::save_to_file(OUT_EQ, &self.eq_expls, |_| (), settings);
-        save_to_json(OUT_EQ_JSON, &self.eq_expls);
-        println!(
+        save_to_json(OUT_EQ_JSON, &info, &self.eq_expls);
+        log::info!(
             "Finished printing eq-expls ({}) after {} seconds",
             self.eq_expls.len(),
             now.elapsed().as_secs_f32()
         );
 
+        let export_data = crate::binary_export::ExportDataRef {
+            instantiations: &instantiations,
+            dependencies: &dependencies,
+            ..self.export_data()
+        };
+
+        // save compact binary export
+        if settings.save_binary {
+            if let Err(e) = crate::binary_export::save_binary(OUT_BINARY, &export_data) {
+                log::error!("{}", e);
+            }
+            log::info!(
+                "Finished binary export after {} seconds",
+                now.elapsed().as_secs_f32()
+            );
+        }
+
+        // save SQLite database
+        if settings.save_sqlite {
+            if let Err(e) = crate::sqlite_export::save_sqlite(OUT_SQLITE, &export_data) {
+                log::error!("{}", e);
+            }
+            log::info!(
+                "Finished SQLite export after {} seconds",
+                now.elapsed().as_secs_f32()
+            );
+        }
+
         // make dot output
-        crate::dot_output::output_dot_and_css_to_file(OUT_DOT, OUT_CSS, &sorted_deps); // sorted option
-        println!(
+        let graph_deps = self.graph_dependencies(&excluded);
+        crate::dot_output::output_dot_and_css_to_file(OUT_DOT, OUT_CSS, &graph_deps, self, settings);
+        log::info!(
             "Finished dot sequence after {} seconds",
             now.elapsed().as_secs_f32()
         );
+
+        // aggregate graph by quantifier
+        if settings.quant_graph {
+            let quant_graph = self.quant_graph(&excluded);
+            crate::dot_output::output_quant_dot_to_file(OUT_QUANT_DOT, &quant_graph);
+            save_to_json_value(OUT_QUANT_GRAPH_JSON, &info, &quant_graph);
+            log::info!(
+                "Finished quantifier graph ({} quantifiers) after {} seconds",
+                quant_graph.nodes.len(),
+                now.elapsed().as_secs_f32()
+            );
+        }
+
+        // extract the subgraph around the chosen instantiation
+        if settings.subgraph_center != 0 {
+            let max_depth = (settings.subgraph_depth > 0).then_some(settings.subgraph_depth);
+            match self.subgraph(settings.subgraph_center, &settings.subgraph_direction, max_depth) {
+                Ok(subgraph) => {
+                    crate::dot_output::output_subgraph_dot_to_file(OUT_SUBGRAPH_DOT, &subgraph, self, &settings.node_size);
+                    save_to_json_value(OUT_SUBGRAPH_JSON, &info, &subgraph);
+                    log::info!(
+                        "Finished subgraph ({} instantiations) after {} seconds",
+                        subgraph.nodes.len(),
+                        now.elapsed().as_secs_f32()
+                    );
+                },
+                Err(e) => {
+                    log::error!("{}", e);
+                    // do not leave (or render) a subgraph from an earlier run
+                    for filename in [OUT_SUBGRAPH_DOT, OUT_SUBGRAPH_SVG, OUT_SUBGRAPH_JSON] {
+                        let _ = std::fs::remove_file(filename);
+                    }
+                },
+            }
+        }
         
     }
 }
 
-/// Save contents of BTreeMap `map` to a JSON file named `filename`
-fn save_to_json<K, V>(filename: &str, map: &BTreeMap<K, V>) where K: Serialize, V: Serialize {
-    let json = serde_json::to_string_pretty(&map).unwrap_or_else(|err| panic!("{}", err));
+/// Save contents of BTreeMap `map` to a JSON file named `filename`, wrapped in an envelope with export metadata `info`
+fn save_to_json<K, V>(filename: &str, info: &ExportInfo, map: &BTreeMap<K, V>) where K: Serialize, V: Serialize {
+    let json = to_json_envelope(info, map);
     let mut json_file = open_file_truncate(filename);
     json_file
         .write_all(json.as_bytes())
         .expect("Write should succeed");
 }
 
-/// Save contents of Vec `vec` to a JSON file named `filename`
-fn save_to_json_vec<T>(filename: &str, vec: &[T]) where T: Serialize {
-    let json = serde_json::to_string_pretty(&vec).unwrap_or_else(|err| panic!("{}", err));
+/// Save `value` to a JSON file named `filename`, wrapped in an envelope with export metadata `info`
+fn save_to_json_value<T>(filename: &str, info: &ExportInfo, value: &T) where T: Serialize {
+    let json = to_json_envelope(info, value);
+    let mut json_file = open_file_truncate(filename);
+    json_file
+        .write_all(json.as_bytes())
+        .expect("Write should succeed");
+}
+
+/// Save contents of Vec `vec` to a JSON file named `filename`, wrapped in an envelope with export metadata `info`
+fn save_to_json_vec<T>(filename: &str, info: &ExportInfo, vec: &[T]) where T: Serialize {
+    let json = to_json_envelope(info, vec);
     let mut json_file = open_file_truncate(filename);
     json_file
         .write_all(json.as_bytes())
@@ -588,9 +809,7 @@ fn save_terms_to_txt(settings: &Settings, terms_main: &BTreeMap<usize, Term>) {
         let mut file2 = open_file_truncate(OUT_PRETTY_TERMS);
         for i in 0..terms_main.len() {
             let t = terms_main.get(&(i + 1)).unwrap();
-            if settings.verbose {
-                t.print();
-            }
+            t.trace();
             write(&mut file, t);
             write_str(&mut file2, &format!("{}\n", &t.text));
         }
@@ -602,8 +821,6 @@ fn save_terms_to_txt(settings: &Settings, terms_main: &BTreeMap<usize, Term>) {
 impl Default for Z3Parser1 {
     /// Creates a new Z3Parser1
     fn default() -> Z3Parser1 {
-        let qvar_re_1 = Regex::new(QVAR_REGEX_STR_1).unwrap();
-        let qvar_re_2 = Regex::new(QVAR_REGEX_STR_2).unwrap();
         Z3Parser1 {
             terms: TwoDMap(HashMap::new()),
             quantifiers: TwoDMap(HashMap::new()),
@@ -615,14 +832,42 @@ impl Default for Z3Parser1 {
             fingerprints: BTreeMap::new(),
             dependencies: vec![],
             version_info: VersionInfo::default(),
-            continue_parsing: Arc::new(Mutex::new(true)),
-            qvar_re: vec![qvar_re_1, qvar_re_2],
+            format: LogFormat::default(),
+            lines_read: 0,
+            log_prefix: LogPrefix::default(),
+            continue_parsing: new_continue_mutex(),
+            qvar_re: new_qvar_regexes(),
+            skipped_prefix: LogPrefix::default(),
+            streamed: (0, 0),
+            tracker: None,
+            unknown_line_cases: BTreeMap::new(),
+            sources: None,
+            sources_file: String::new(),
         }
     }
 }
 
+fn new_continue_mutex() -> Arc<Mutex<bool>> {
+    Arc::new(Mutex::new(true))
+}
+
+fn new_qvar_regexes() -> Vec<Regex> {
+    let qvar_re_1 = Regex::new(QVAR_REGEX_STR_1).unwrap();
+    let qvar_re_2 = Regex::new(QVAR_REGEX_STR_2).unwrap();
+    vec![qvar_re_1, qvar_re_2]
+}
+
 impl Z3Parser1 {
-    fn update_costs(&mut self) {
+    pub(crate) fn update_costs(&mut self) {
+        // reset costs so that repeated updates (e.g. when following a growing log) do not accumulate
+        for inst in self.instantiations.values_mut() {
+            inst.cost = 1.0;
+        }
+        for ns_map in self.quantifiers.0.values_mut() {
+            for quant in ns_map.values_mut() {
+                quant.cost = quant.instances.len() as f32;
+            }
+        }
         // propagate cost data
         let mut cost_data: Vec<(Vec<usize>, usize)> = self
             .instantiations
@@ -630,7 +875,7 @@ impl Z3Parser1 {
             .map(|inst| (inst.dep_instantiations.clone(), inst.line_no))
             .collect();
         // grab dep inst line #s and own line #s
-        cost_data.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+        cost_data.sort_unstable_by_key(|a| a.1);
         // sort by their line numbers
         cost_data.reverse();
         // start from latest instantiations and go backwards
@@ -655,7 +900,7 @@ impl Z3Parser1 {
 
     /// Add a (partial) blank instantiation dependency. Used to keep track of instantiations dependent on no others.
     fn add_blank_dependency_if_needed(
-        dep_insts: &mut Vec<usize>,
+        dep_insts: &mut [usize],
         quant_id: &str,
         temp_deps: &mut BTreeMap<usize, Vec<Dependency>>,
         match_line: usize,
@@ -717,9 +962,7 @@ impl Z3Parser1 {
             let mut file = open_file_truncate(filename);
             for item in map.values() {
                 do_stuff(item);
-                if settings.verbose {
-                    item.print();
-                }
+                item.trace();
                 write(&mut file, item);
             }
             file.flush().unwrap();
@@ -736,9 +979,7 @@ impl Z3Parser1 {
             let mut file = open_file_truncate(filename);
             for item in vec {
                 do_stuff(item);
-                if settings.verbose {
-                    item.print();
-                }
+                item.trace();
                 write(&mut file, item);
             }
             file.flush().unwrap();
@@ -746,11 +987,10 @@ impl Z3Parser1 {
     }
 
     /// Returns a vector of instantiations, sorted by cost
-    fn sort_instantiations_by_cost(
-        instantiations: &BTreeMap<usize, Instantiation>,
+    fn sort_instantiations_by_cost<'a>(
+        instantiations: impl Iterator<Item = &'a Instantiation>,
     ) -> Vec<Instantiation> {
         let mut inst_vec = instantiations
-            .values()
             .cloned()
             .collect::<Vec<Instantiation>>();
         inst_vec.sort_unstable_by(|inst1, inst2| inst2.cost.partial_cmp(&inst1.cost).unwrap());
@@ -758,13 +998,13 @@ impl Z3Parser1 {
     }
 
     /// Show only the `max_elements` most costly instantiations
-    fn filter_instantiations_by_cost(
-        instantiations: &BTreeMap<usize, Instantiation>,
+    pub(crate) fn filter_instantiations_by_cost<'a>(
+        instantiations: impl Iterator<Item = &'a Instantiation>,
         max_elements: usize,
     ) -> Vec<Instantiation> {
-        Z3Parser1::sort_instantiations_by_cost(instantiations)
-            [..std::cmp::min(instantiations.len(), max_elements)]
-            .to_vec()
+        let mut inst_vec = Z3Parser1::sort_instantiations_by_cost(instantiations);
+        inst_vec.truncate(max_elements);
+        inst_vec
     }
 
     /// Filter dependencies so that only the most constly instantiations are featured
@@ -785,24 +1025,400 @@ impl Z3Parser1 {
             let mut file = open_file_truncate(OUT_QUANT);
             let mut file2 = open_file_truncate(OUT_PRETTY_QUANT);
             for q in quantifiers_main.values() {
-                if settings.verbose {
-                    q.print();
-                }
+                q.trace();
                 write(&mut file, q);
                 write_str(&mut file2, &(q.pretty_text(&self.terms) + "\n"));
+                let source = self.sources.as_ref().map_or_else(String::new, |sources| sources.describe(&q.name));
+                if !source.is_empty() {
+                    write_str(&mut file2, &format!("    {}\n", source.replace('\n', "\n    ")));
+                }
             }
             file.flush().unwrap();
             file2.flush().unwrap();
         }
     }
 
-    pub fn get_sorted_dependencies(&self) -> Vec<Dependency> {
-        let insts_sorted = Z3Parser1::filter_instantiations_by_cost(&self.instantiations, 250);
-        Self::filter_dependencies_by_cost(&insts_sorted, &self.dependencies)
-    } 
+    /// Line numbers of the instantiations left out by `filter`.
+    pub fn excluded_instantiations(&self, filter: &QuantFilter) -> HashSet<usize> {
+        self.instantiations.values()
+            .filter(|inst| {
+                let name = self.quantifiers.get(&inst.quant_id).map_or(inst.quant_id.as_str(), |quant| quant.name.as_str());
+                !filter.keeps(&inst.quant_id, name)
+            })
+            .map(|inst| inst.line_no)
+            .collect()
+    }
+
+    /// Number of `excluded` instantiations by quantifier ID.
+    pub fn excluded_counts(&self, excluded: &HashSet<usize>) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for line_no in excluded {
+            if let Some(inst) = self.instantiations.get(line_no) {
+                *counts.entry(inst.quant_id.clone()).or_default() += 1;
+            }
+        }
+        counts
+    }
+
+    /// Instantiations and dependencies without the `excluded` instantiations and the dependencies on or of them.
+    pub(crate) fn without_excluded(&self, excluded: &HashSet<usize>) -> (BTreeMap<usize, Instantiation>, Vec<Dependency>) {
+        let instantiations = self.instantiations.iter()
+            .filter(|(line_no, _)| !excluded.contains(line_no))
+            .map(|(line_no, inst)| (*line_no, inst.clone()))
+            .collect();
+        let dependencies = self.dependencies.iter()
+            .filter(|dep| !excluded.contains(&dep.from) && !excluded.contains(&dep.to))
+            .cloned()
+            .collect();
+        (instantiations, dependencies)
+    }
+
+    /// Dependencies of the 250 most costly instantiations that are not `excluded`.
+    /// Dependencies on excluded instantiations are kept without their source (`from` is 0),
+    /// so that the instantiations depending on them are still shown.
+    pub fn graph_dependencies(&self, excluded: &HashSet<usize>) -> Vec<Dependency> {
+        let kept = self.instantiations.values().filter(|inst| !excluded.contains(&inst.line_no));
+        let insts_sorted = Z3Parser1::filter_instantiations_by_cost(kept, 250);
+        let mut deps = Self::filter_dependencies_by_cost(&insts_sorted, &self.dependencies);
+        for dep in deps.iter_mut().filter(|dep| excluded.contains(&dep.from)) {
+            dep.from = 0;
+            dep.blamed = String::new();
+            dep.dep_type = DepType::None;
+        }
+        deps
+    }
+
+    /// Builds a self-contained HTML report with the rendered graph `svg` and the details of every instantiation in it.
+    pub fn get_html_report(&self, svg: &str, settings: &Settings) -> String {
+        use crate::html_report::{ReportData, make_report};
+        let excluded = self.excluded_instantiations(&QuantFilter::from_settings(settings));
+        let sorted_deps = self.graph_dependencies(&excluded);
+        let mut data = ReportData::default();
+        for dep in &sorted_deps {
+            for line_no in [dep.from, dep.to] {
+                if let Some(inst) = self.instantiations.get(&line_no) {
+                    if !data.instantiations.contains_key(&line_no) {
+                        data.add(inst, &self.terms, &self.quantifiers, &self.eq_expls, self.sources.as_ref());
+                    }
+                }
+            }
+        }
+        let css = crate::dot_output::get_css_output_as_string(&sorted_deps, self, settings);
+        let legend = crate::dot_output::get_legend(&sorted_deps, self, settings);
+        make_report(&settings.file, svg, &css, &legend, &data)
+    }
+
+    /// Reads the quantifiers of `settings.smt2_file` (if any) to show where the logged quantifiers are written,
+    /// unless they have already been read (e.g. by an earlier refresh in follow mode).
+    /// Outputs are saved without sources if the file cannot be read.
+    pub(crate) fn load_sources(&mut self, settings: &Settings) {
+        if settings.smt2_file == self.sources_file {
+            return;
+        }
+        self.sources_file = settings.smt2_file.clone();
+        self.sources = None;
+        if settings.smt2_file.is_empty() {
+            return;
+        }
+        match SourceMap::from_file(&settings.smt2_file) {
+            Ok(sources) => {
+                let found = self.quantifiers.0.values().flat_map(|quants| quants.values()).filter(|q| sources.find(&q.name).is_some()).count();
+                log::info!("Found {} of the logged quantifiers in {}", found, settings.smt2_file);
+                self.sources = Some(sources);
+            },
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+
+    /// Graph of quantifiers, with edges counting how often instantiations of one triggered instantiations of another.
+    pub fn quant_graph(&self, excluded: &HashSet<usize>) -> QuantGraph {
+        QuantGraph::build(&self.quantifiers, &self.instantiations, &self.dependencies, excluded)
+    }
+
+    /// Instantiations at most `max_depth` dependencies (any number if `None`) before and/or after the one at line `center`.
+    pub fn subgraph(&self, center: usize, direction: &SubgraphDirection, max_depth: Option<usize>) -> Result<Subgraph, String> {
+        Subgraph::extract(&self.dependencies, center, direction, max_depth)
+    }
+
+    pub fn get_subgraph_dot(&self, subgraph: &Subgraph, node_size: &NodeSize) -> String {
+        crate::dot_output::get_subgraph_dot(subgraph, self, node_size)
+    }
+
+    pub fn get_dot_output_as_string(&self, settings: &Settings) -> String {
+        let excluded = self.excluded_instantiations(&QuantFilter::from_settings(settings));
+        let graph_deps = self.graph_dependencies(&excluded);
+        crate::dot_output::get_dot_output_as_string(&graph_deps, self, settings)
+    }
+
+    /// Exports terms, quantifiers, instantiations, dependencies and equality explanations
+    /// to a compact binary file, which can be loaded with `binary_export::load_binary`.
+    pub fn export_binary(&self, filename: &str) -> Result<(), String> {
+        crate::binary_export::save_binary(filename, &self.export_data())
+    }
+
+    /// Exports terms, quantifiers, instantiations (with their bound, blamed and yielded terms),
+    /// equality explanations and dependencies to normalized tables of an SQLite database.
+    pub fn export_sqlite(&self, filename: &str) -> Result<(), String> {
+        crate::sqlite_export::save_sqlite(filename, &self.export_data())
+    }
+
+    /// Parser holding items read by another parser (e.g. `cvc5parser::Cvc5Parser`),
+    /// so that they can be saved, exported and rendered like those of a Z3 log.
+    pub(super) fn from_items(
+        terms: TwoDMap<Term>,
+        quantifiers: TwoDMap<Quantifier>,
+        instantiations: BTreeMap<usize, Instantiation>,
+        dependencies: Vec<Dependency>,
+        version_info: VersionInfo,
+        lines_read: usize,
+    ) -> Z3Parser1 {
+        Z3Parser1 { terms, quantifiers, instantiations, dependencies, version_info, lines_read, ..Z3Parser1::default() }
+    }
+
+    /// Parsed instantiations by line number.
+    pub fn instantiations(&self) -> &BTreeMap<usize, Instantiation> {
+        &self.instantiations
+    }
+
+    /// Parsed terms by namespace and ID number.
+    pub fn terms(&self) -> &TwoDMap<Term> {
+        &self.terms
+    }
+
+    /// Parsed quantifiers by namespace and ID number.
+    pub fn quantifiers(&self) -> &TwoDMap<Quantifier> {
+        &self.quantifiers
+    }
+
+    /// Parsed equality explanations by term ID.
+    pub fn eq_expls(&self) -> &BTreeMap<String, EqualityExpl> {
+        &self.eq_expls
+    }
+
+    /// Solver and version that wrote the log.
+    pub fn version_info(&self) -> &VersionInfo {
+        &self.version_info
+    }
+
+    /// Quantifiers of `settings.smt2_file`, once read by `load_sources`.
+    pub fn sources(&self) -> Option<&SourceMap> {
+        self.sources.as_ref()
+    }
+
+    /// Borrowed view of all parsed results, for exporting.
+    pub(crate) fn export_data(&self) -> crate::binary_export::ExportDataRef<'_> {
+        crate::binary_export::ExportDataRef {
+            terms: &self.terms,
+            quantifiers: &self.quantifiers,
+            instantiations: &self.instantiations,
+            dependencies: &self.dependencies,
+            eq_expls: &self.eq_expls,
+        }
+    }
+
+    /// Reports progress to `observer` while parsing (every `interval`), once parsing has finished and at the start
+    /// of each phase. If the observer streams batches, the new results are sent to it along with each report while parsing.
+    pub fn observe_progress(&mut self, observer: Box<dyn ProgressObserver>, interval: ReportInterval) {
+        self.tracker = Some(ProgressTracker::new(observer, interval));
+    }
+
+    /// Number of lines skipped for each line case the parser does not handle, since parsing last finished
+    /// (when a summary of them is logged).
+    pub fn unknown_line_cases(&self) -> &BTreeMap<String, usize> {
+        &self.unknown_line_cases
+    }
+
+    /// Number of terms in all namespaces.
+    fn term_count(&self) -> usize {
+        self.terms.0.values().map(BTreeMap::len).sum()
+    }
+
+    /// Reports the current progress to the observer, if there is one.
+    fn report_now(&mut self) {
+        let (instantiations, terms) = (self.instantiations.len(), self.term_count());
+        if let Some(tracker) = &mut self.tracker {
+            tracker.report_now(self.lines_read, instantiations, terms);
+        }
+    }
+
+    /// Streams the instantiations before line `end` and dependencies found since the last batch to the observer,
+    /// if it streams batches.
+    fn send_batch_until(&mut self, end: usize) {
+        let Some(tracker) = self.tracker.as_mut().filter(|tracker| tracker.streams_batches()) else { return };
+        let (last_line, sent_deps) = self.streamed;
+        let instantiations: Vec<Instantiation> = self.instantiations.range(last_line + 1..end.max(last_line + 1)).map(|(_, inst)| inst.clone()).collect();
+        let dependencies = self.dependencies[sent_deps..].to_vec();
+        if instantiations.is_empty() && dependencies.is_empty() {
+            return;
+        }
+        self.streamed = (instantiations.last().map_or(last_line, |inst| inst.line_no), self.dependencies.len());
+        tracker.send_batch(Batch { instantiations, dependencies });
+    }
+
+    /// Saves the full parser state to a compact binary snapshot file, including the number of lines read.
+    /// Parsing the same (possibly since extended) log with a parser loaded from the snapshot continues from there.
+    pub fn save_snapshot(&self, filename: &str) -> Result<(), String> {
+        let file = std::fs::File::create(filename).map_err(|e| format!("Error opening {}: {}", filename, e))?;
+        crate::binary_export::write_versioned(io::BufWriter::new(file), SNAPSHOT_MAGIC, SNAPSHOT_VERSION, self)
+    }
+
+    /// Restores a parser from a snapshot file written by `save_snapshot`.
+    /// # Errors
+    /// Errors if the file cannot be read, is not a snapshot, or was written by an incompatible version.
+    pub fn load_snapshot(filename: &str) -> Result<Z3Parser1, String> {
+        let file = std::fs::File::open(filename).map_err(|e| format!("Error opening {}: {}", filename, e))?;
+        crate::binary_export::read_versioned(io::BufReader::new(file), SNAPSHOT_MAGIC, SNAPSHOT_VERSION, "parser snapshot")
+            .map_err(|e| format!("{}: {}", filename, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_logs::{self, parse};
+
+    #[test]
+    fn test_parse_from_string() {
+        let parser = parse(Log::File(test_logs::SIMPLE.to_string()));
+        assert_eq!(parser.instantiations.keys().copied().collect::<Vec<_>>(), vec![10, 17]);
+        let edges: Vec<(usize, usize)> = parser.dependencies.iter().map(|dep| (dep.from, dep.to)).collect();
+        assert_eq!(edges, vec![(0, 10), (10, 17)]);
+    }
+
+    #[test]
+    fn test_log_versions() {
+        let parser = parse(Log::File(test_logs::Z3_4_8_4.to_string()));
+        assert_eq!(parser.format, LogFormat::Legacy);
+        let edges: Vec<(usize, usize)> = parser.dependencies.iter().map(|dep| (dep.from, dep.to)).collect();
+        assert_eq!(edges, vec![(0, 9), (9, 16)]);
+        assert!(matches!(parser.instantiations[&16].blamed_terms[..], [BlamedTermItem::Single(ref id)] if id == "#7"));
+
+        let parser = parse(Log::File(test_logs::Z3_4_8_17.to_string()));
+        assert_eq!(parser.format, LogFormat::Current);
+        assert_eq!(parser.instantiations[&11].fingerprint, 0x7f3a00000001);
+        assert_eq!(parser.instantiations[&19].quant_id, "arith#");
+        assert_eq!(parser.quantifiers.get("#10").unwrap().name, "lambda");
+        let edges: Vec<(usize, usize)> = parser.dependencies.iter().map(|dep| (dep.from, dep.to)).collect();
+        assert_eq!(edges, vec![(0, 11), (11, 19), (11, 22)]);
+
+        let version = |solver: &str, version: &str| VersionInfo { solver: solver.to_string(), version: version.to_string() };
+        assert_eq!(version("Z3", "4.8.4").log_format(), LogFormat::Legacy);
+        assert!(version("Z3", "4.8.4").support_warning().unwrap().contains("not supported"));
+        assert_eq!(version("Z3", "4.8.5").support_warning(), None);
+        assert_eq!(version("Z3", "4.12.1").support_warning(), None);
+        assert!(version("Z3", "4.15.0").support_warning().unwrap().contains("newer"));
+        assert!(version("Z3", "unstable").support_warning().is_some());
+        assert!(version("cvc5", "1.0").support_warning().is_some());
+        assert!(VersionInfo::default().support_warning().unwrap().contains("no [tool-version] line"));
+    }
+
+    #[test]
+    fn test_parse_from_reader() {
+        let reader = io::Cursor::new(test_logs::SIMPLE.as_bytes().to_vec());
+        let parser = parse(Log::Reader(Box::new(reader)));
+        let expected = parse(Log::File(test_logs::SIMPLE.to_string()));
+        assert_eq!(parser.instantiations.len(), expected.instantiations.len());
+        assert_eq!(parser.dependencies.len(), expected.dependencies.len());
+        // read errors, e.g. invalid UTF-8 on standard input, are returned
+        let reader = io::Cursor::new(b"[tool-version] Z3 4.12.1\n\xff\n".to_vec());
+        let error = Z3Parser1::default().main_parse_loop(Log::Reader(Box::new(reader))).unwrap_err();
+        assert!(error.starts_with("Error reading line 1"), "{}", error);
+        let error = Z3Parser1::default().main_parse_loop(Log::Filename(String::from("/nonexistent/z3.log"))).unwrap_err();
+        assert!(error.starts_with("Error opening /nonexistent/z3.log"), "{}", error);
+    }
+
+    #[test]
+    fn test_follow_refreshes_while_data_arrives() {
+        // the whole log is available at once, so the parser never waits for new data
+        let reader = io::Cursor::new(test_logs::SIMPLE.as_bytes().to_vec());
+        let mut refreshes = 0;
+        let mut parser = Z3Parser1::default();
+        parser.follow_parse_loop(reader, Duration::from_millis(5), Duration::from_nanos(1), |_| refreshes += 1).unwrap();
+        assert_eq!(parser.instantiations.len(), 2);
+        assert!(refreshes > 0);
+    }
+
+    #[test]
+    fn test_follow_growing_file() {
+        let path = std::env::temp_dir().join(format!("follow_test_{}.log", std::process::id()));
+        let (first, rest) = test_logs::SIMPLE.split_at(test_logs::SIMPLE.find("[new-match] 2").unwrap());
+        std::fs::write(&path, first).unwrap();
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            let mut file = std::fs::OpenOptions::new().append(true).open(writer_path).unwrap();
+            // write the rest in two pieces, splitting a line in half
+            let (a, b) = rest.split_at(5);
+            file.write_all(a.as_bytes()).unwrap();
+            file.flush().unwrap();
+            std::thread::sleep(Duration::from_millis(30));
+            file.write_all(b.as_bytes()).unwrap();
+        });
+        let mut parser = Z3Parser1::default();
+        let mut refreshes = 0;
+        let file = std::fs::File::open(&path).unwrap();
+        parser.follow_parse_loop(io::BufReader::new(file), Duration::from_millis(5), Duration::from_millis(1), |_| refreshes += 1).unwrap();
+        writer.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(parser.instantiations.len(), 2);
+        assert_eq!(parser.dependencies.len(), 2);
+        assert!(refreshes > 0);
+    }
+
+    #[test]
+    fn test_unknown_line_cases() {
+        let log = test_logs::SIMPLE.replace("[eof]", "[new-case] 1\n[new-case] 2\n[inst-discovered] guess 0 #5 ; #1\n[eof]");
+        let mut parser = Z3Parser1::default();
+        parser.parse_lines(log.lines().map(Ok::<_, io::Error>)).unwrap();
+        let expected = BTreeMap::from([(String::from("[inst-discovered] guess"), 1), (String::from("[new-case]"), 2)]);
+        assert_eq!(parser.unknown_line_cases(), &expected);
+        // the summary is logged once, when parsing finishes
+        parser.parsing_finished();
+        assert!(parser.unknown_line_cases().is_empty());
+    }
+
+    #[test]
+    fn test_update_costs_is_repeatable() {
+        let mut parser = parse(Log::File(test_logs::SIMPLE.to_string()));
+        parser.update_costs();
+        let costs: Vec<f32> = parser.instantiations.values().map(|inst| inst.cost).collect();
+        parser.update_costs();
+        assert_eq!(parser.instantiations.values().map(|inst| inst.cost).collect::<Vec<f32>>(), costs);
+        assert_eq!(costs, vec![2.0, 1.0]);
+    }
+
+    #[test]
+    fn test_snapshot_resume() {
+        let path = std::env::temp_dir().join(format!("snapshot_test_{}.snap", std::process::id()));
+        let path = path.to_str().unwrap();
+        let (first, _) = test_logs::SIMPLE.split_at(test_logs::SIMPLE.find("[new-match] 2").unwrap());
+        let partial = parse(Log::File(first.to_string()));
+        assert_eq!(partial.instantiations.len(), 1);
+        partial.save_snapshot(path).unwrap();
+        let mut resumed = Z3Parser1::load_snapshot(path).unwrap();
+        assert_eq!(resumed.lines_processed(), first.lines().count());
+        resumed.main_parse_loop(Log::File(test_logs::SIMPLE.to_string())).unwrap();
+        let expected = parse(Log::File(test_logs::SIMPLE.to_string()));
+        assert_eq!(resumed.instantiations.keys().collect::<Vec<_>>(), expected.instantiations.keys().collect::<Vec<_>>());
+        assert_eq!(resumed.dependencies.len(), expected.dependencies.len());
+        assert_eq!(resumed.terms.get("#9"), expected.terms.get("#9"));
+
+        // the snapshot is only resumed on a log starting with the lines parsed before
+        let other = test_logs::SIMPLE.replacen("[mk-app] #1 a", "[mk-app] #1 b", 1);
+        let error = Z3Parser1::load_snapshot(path).unwrap().main_parse_loop(Log::File(other)).unwrap_err();
+        assert!(error.contains("differ from those parsed before"), "{}", error);
+        let shorter = first.lines().take(3).collect::<Vec<_>>().join("\n");
+        let error = Z3Parser1::load_snapshot(path).unwrap().main_parse_loop(Log::File(shorter)).unwrap_err();
+        assert!(error.contains("has only 3 lines"), "{}", error);
+        std::fs::remove_file(path).unwrap();
+    }
 
-    pub fn get_dot_output_as_string(&self) -> String {
-        let sorted_deps = self.get_sorted_dependencies();
-        crate::dot_output::get_dot_output_as_string(&sorted_deps)
+    #[test]
+    fn test_load_snapshot_rejects_other_files() {
+        let path = std::env::temp_dir().join(format!("not_a_snapshot_{}.snap", std::process::id()));
+        std::fs::write(&path, "[tool-version] Z3 4.12.1\n").unwrap();
+        let result = Z3Parser1::load_snapshot(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
     }
 }
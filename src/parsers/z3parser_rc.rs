@@ -68,7 +68,7 @@ impl Z3LogParser for Z3ParserRc {
             solver: l[1].to_string(),
             version: l[2].to_string(),
         };
-        println!(
+        log::info!(
             "{} {}",
             &self._version_info.solver, &self._version_info.version
         );
@@ -150,7 +150,7 @@ impl Z3LogParser for Z3ParserRc {
 
     fn conflict(&mut self, _l: &[&str]) {}
 
-    fn main_parse_loop(&mut self, log: Log) {
+    fn main_parse_loop(&mut self, log: Log) -> Result<(), String> {
         match log {
             Log::Filename(filename) => {
                 if let Ok(lines) = read_lines(filename) {
@@ -228,14 +228,16 @@ impl Z3LogParser for Z3ParserRc {
                             "[conflict]" => {
                                 self.conflict(&l);
                             }
-                            _ => println!("Unknown line case: {}", l0),
+                            _ => log::debug!("Unknown line case: {}", l0),
                         }
                     }
                 } else {
                     panic!("Failed reading lines")
                 }
+                Ok(())
             },
-            Log::File(_) => todo!() 
+            Log::File(file) => self.parse_lines(file.lines().map(Ok)),
+            Log::Reader(reader) => self.parse_lines(reader.lines()),
         } 
     }
 
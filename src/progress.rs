@@ -0,0 +1,217 @@
+use std::io::{self, Write};
+use serde::Serialize;
+
+use crate::items::{Dependency, Instantiation};
+
+/// Stage of processing a log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Parsing,
+    /// computing the costs of instantiations and quantifiers
+    Costing,
+    /// saving the text, JSON, binary and database outputs and the Dot files
+    Exporting,
+    /// rendering the graphs
+    Rendering,
+    /// all outputs are saved
+    Done,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Parsing => "parsing",
+            Phase::Costing => "costing",
+            Phase::Exporting => "exporting",
+            Phase::Rendering => "rendering",
+            Phase::Done => "done",
+        }
+    }
+}
+
+/// Progress of processing a log at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressReport {
+    pub phase: Phase,
+    /// number of log lines parsed, including those skipped when resuming from a snapshot
+    pub lines_read: usize,
+    /// bytes of the log parsed in this run (line breaks counted as one byte)
+    pub bytes_read: u64,
+    /// size of the log in bytes, 0 if unknown (e.g. when reading standard input)
+    pub total_bytes: u64,
+    pub instantiations: usize,
+    pub terms: usize,
+}
+
+/// Results found by a parser since the previous batch.
+/// Costs are only final once the parse is done, so the costs of streamed instantiations are preliminary.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Batch {
+    pub instantiations: Vec<Instantiation>,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// Receives progress reports from a parser, e.g. to show a progress bar.
+pub trait ProgressObserver: Send + Sync {
+    /// Called at the start of each phase, while parsing every `ReportInterval`, and once parsing has finished.
+    fn on_progress(&mut self, report: &ProgressReport);
+
+    /// Whether the observer also receives the new results (see `on_batch`).
+    fn streams_batches(&self) -> bool {
+        false
+    }
+
+    /// Called with the results found since the previous batch after each report while parsing, if `streams_batches`.
+    fn on_batch(&mut self, _batch: Batch) {}
+}
+
+/// How often an observer is called while parsing: after this many lines or bytes, whichever comes first (0 for never).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportInterval {
+    pub lines: usize,
+    pub bytes: u64,
+}
+
+/// Interval used by the command line progress bar.
+pub const DEFAULT_REPORT_INTERVAL: ReportInterval = ReportInterval { lines: 100_000, bytes: 1 << 20 };
+
+/// Keeps track of a parser's progress and calls its observer when reports are due.
+pub struct ProgressTracker {
+    observer: Box<dyn ProgressObserver>,
+    interval: ReportInterval,
+    report: ProgressReport,
+    /// lines and bytes parsed since the last report
+    since_report: (usize, u64),
+}
+
+impl ProgressTracker {
+    pub fn new(observer: Box<dyn ProgressObserver>, interval: ReportInterval) -> ProgressTracker {
+        let report = ProgressReport {
+            phase: Phase::Parsing,
+            lines_read: 0,
+            bytes_read: 0,
+            total_bytes: 0,
+            instantiations: 0,
+            terms: 0,
+        };
+        ProgressTracker { observer, interval, report, since_report: (0, 0) }
+    }
+
+    /// Records the size of the log.
+    pub fn set_total_bytes(&mut self, total_bytes: u64) {
+        self.report.total_bytes = total_bytes;
+    }
+
+    /// Records that a line `len` bytes long (without its line break) was parsed. Returns whether a report is due.
+    pub fn line_processed(&mut self, len: usize) -> bool {
+        self.report.bytes_read += len as u64 + 1;
+        self.since_report.0 += 1;
+        self.since_report.1 += len as u64 + 1;
+        let (lines, bytes) = self.since_report;
+        (self.interval.lines > 0 && lines >= self.interval.lines) || (self.interval.bytes > 0 && bytes >= self.interval.bytes)
+    }
+
+    /// Reports the start of `phase`.
+    pub fn start_phase(&mut self, phase: Phase, lines_read: usize, instantiations: usize, terms: usize) {
+        self.report.phase = phase;
+        self.report_now(lines_read, instantiations, terms);
+    }
+
+    /// Reports the current progress.
+    pub fn report_now(&mut self, lines_read: usize, instantiations: usize, terms: usize) {
+        self.report.lines_read = lines_read;
+        self.report.instantiations = instantiations;
+        self.report.terms = terms;
+        self.since_report = (0, 0);
+        self.observer.on_progress(&self.report);
+    }
+
+    /// Whether the observer receives batches of new results.
+    pub fn streams_batches(&self) -> bool {
+        self.observer.streams_batches()
+    }
+
+    /// Passes `batch` to the observer.
+    pub fn send_batch(&mut self, batch: Batch) {
+        self.observer.on_batch(batch);
+    }
+}
+
+/// Progress bar on standard error, redrawn in place.
+#[derive(Debug, Default)]
+pub struct ProgressBar {
+    /// what was last drawn, to skip redrawing the same text
+    last: String,
+}
+
+/// number of characters of the bar itself
+const BAR_WIDTH: usize = 30;
+
+impl ProgressBar {
+    pub fn new() -> ProgressBar {
+        ProgressBar::default()
+    }
+}
+
+impl ProgressObserver for ProgressBar {
+    fn on_progress(&mut self, report: &ProgressReport) {
+        let text = format_bar(report);
+        if text == self.last {
+            return;
+        }
+        let mut stderr = io::stderr().lock();
+        // pad to overwrite a longer previous line
+        let _ = write!(stderr, "\r{:width$}", text, width = self.last.chars().count());
+        if report.phase == Phase::Done {
+            let _ = writeln!(stderr);
+        }
+        let _ = stderr.flush();
+        self.last = text;
+    }
+}
+
+/// Text of a progress bar for `report`. While parsing, shows how much of the log has been parsed if its size is known.
+pub fn format_bar(report: &ProgressReport) -> String {
+    let counts = format!("{} lines, {} instantiations, {} terms", report.lines_read, report.instantiations, report.terms);
+    if report.total_bytes == 0 || report.phase != Phase::Parsing {
+        return format!("{:<9} {}", report.phase.name(), counts);
+    }
+    let fraction = (report.bytes_read as f64 / report.total_bytes as f64).min(1.0);
+    let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+    format!("{:<9} [{}{}] {:>3}% {}", report.phase.name(), "#".repeat(filled), "-".repeat(BAR_WIDTH - filled), (fraction * 100.0).round(), counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::parsers::{test_logs, Log, Z3LogParser};
+    use crate::parsers::z3parser1::Z3Parser1;
+
+    struct Recorder(Arc<Mutex<Vec<ProgressReport>>>);
+
+    impl ProgressObserver for Recorder {
+        fn on_progress(&mut self, report: &ProgressReport) {
+            self.0.lock().unwrap().push(*report);
+        }
+    }
+
+    #[test]
+    fn test_reports() {
+        let reports = Arc::new(Mutex::new(vec![]));
+        let mut parser = Z3Parser1::default();
+        parser.observe_progress(Box::new(Recorder(Arc::clone(&reports))), ReportInterval { lines: 8, bytes: 0 });
+        parser.main_parse_loop(Log::File(test_logs::SIMPLE.to_string())).unwrap();
+        let reports = reports.lock().unwrap();
+        // at the start of parsing, then every 8 lines and when parsing has finished
+        assert_eq!(reports.iter().map(|report| report.lines_read).collect::<Vec<_>>(), vec![0, 8, 16, 20]);
+        let last = reports[2];
+        assert_eq!((last.phase, last.instantiations, last.terms), (Phase::Parsing, 1, 8));
+        assert_eq!(last.total_bytes, test_logs::SIMPLE.len() as u64);
+        let first_lines: usize = test_logs::SIMPLE.lines().take(16).map(|line| line.len() + 1).sum();
+        assert_eq!(last.bytes_read, first_lines as u64);
+        assert_eq!(format_bar(&ProgressReport { bytes_read: last.total_bytes / 2, ..last }),
+            "parsing   [###############---------------]  50% 16 lines, 1 instantiations, 8 terms");
+        assert_eq!(format_bar(&ProgressReport { total_bytes: 0, ..last }), "parsing   16 lines, 1 instantiations, 8 terms");
+    }
+}
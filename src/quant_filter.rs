@@ -0,0 +1,100 @@
+use crate::file_io::Settings;
+
+/// Selects instantiations by the quantifier they instantiate.
+///
+/// Patterns are matched against the quantifier's ID (e.g. `#12`), its namespace (e.g. `datatype`) and its name;
+/// `*` matches any text and `?` any single character. Theory-solving instantiations have the theory as ID
+/// and name (e.g. `arith#`), MBQI instantiations `MBQI`.
+#[derive(Debug, Default, Clone)]
+pub struct QuantFilter {
+    /// if not empty, only quantifiers matching one of these patterns are kept
+    pub include: Vec<String>,
+    /// quantifiers matching one of these patterns are left out
+    pub exclude: Vec<String>,
+}
+
+impl QuantFilter {
+    /// Filter configured by `settings.include_quantifiers` and `settings.exclude_quantifiers`.
+    pub fn from_settings(settings: &Settings) -> QuantFilter {
+        QuantFilter { include: settings.include_quantifiers.clone(), exclude: settings.exclude_quantifiers.clone() }
+    }
+
+    /// Whether instantiations of the quantifier with ID `quant_id` and name `name` are kept.
+    pub fn keeps(&self, quant_id: &str, name: &str) -> bool {
+        let namespace = quant_id.split('#').next().unwrap_or_default();
+        let matches = |pattern: &String| glob_match(pattern, quant_id) || glob_match(pattern, name) || glob_match(pattern, namespace);
+        (self.include.is_empty() || self.include.iter().any(matches)) && !self.exclude.iter().any(matches)
+    }
+}
+
+/// Whether `text` matches the glob `pattern`, in which `*` matches any text and `?` any single character.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // positions to resume from after the last `*`, which is extended one character at a time on a mismatch
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashSet};
+    use crate::parsers::{test_logs::{self, parse}, Log, LogParser};
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("arith#", "arith#"));
+        assert!(glob_match("prelude_*", "prelude_seq_length"));
+        assert!(glob_match("*_ax?", "seq_ax1"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("prelude_*", "my_prelude_x"));
+        assert!(!glob_match("a?c", "ac"));
+    }
+
+    #[test]
+    fn test_keeps() {
+        let filter = QuantFilter { include: vec![], exclude: vec![String::from("arith"), String::from("MBQI"), String::from("tmp_*")] };
+        assert!(!filter.keeps("arith#", "arith#"));
+        assert!(!filter.keeps("MBQI", "MBQI"));
+        assert!(!filter.keeps("#7", "tmp_lemma"));
+        assert!(filter.keeps("#5", "q1"));
+        let filter = QuantFilter { include: vec![String::from("#5"), String::from("seq_*")], exclude: vec![String::from("seq_bad")] };
+        assert!(filter.keeps("#5", "q1"));
+        assert!(filter.keeps("#6", "seq_len"));
+        assert!(!filter.keeps("#7", "seq_bad"));
+        assert!(!filter.keeps("basic#", "basic#"));
+    }
+
+    #[test]
+    fn test_quant_filter() {
+        let mut parser = parse(Log::File(test_logs::SIMPLE.to_string()));
+        parser.update_costs();
+        let excluded = parser.excluded_instantiations(&QuantFilter { include: vec![], exclude: vec![String::from("arith#"), String::from("MBQI")] });
+        assert!(excluded.is_empty());
+        assert_eq!(parser.graph_dependencies(&excluded).len(), parser.get_dependencies().len());
+        let excluded = parser.excluded_instantiations(&QuantFilter { include: vec![String::from("seq_*")], exclude: vec![] });
+        assert_eq!(excluded, HashSet::from([10, 17]));
+        assert_eq!(parser.excluded_counts(&excluded), BTreeMap::from([(String::from("#5"), 2)]));
+        assert!(parser.graph_dependencies(&excluded).is_empty());
+        assert!(parser.quant_graph(&excluded).nodes.is_empty());
+        let (instantiations, dependencies) = parser.without_excluded(&excluded);
+        assert!(instantiations.is_empty() && dependencies.is_empty());
+    }
+}
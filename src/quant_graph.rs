@@ -0,0 +1,88 @@
+use std::collections::{BTreeMap, HashSet};
+use serde::Serialize;
+
+use crate::items::{Dependency, Instantiation, Quantifier, TwoDMap};
+
+/// A quantifier in the quantifier graph.
+#[derive(Debug, Serialize)]
+pub struct QuantNode {
+    /// full ID, e.g. `#12`
+    pub id: String,
+    pub name: String,
+    /// number of instantiations of the quantifier
+    pub instances: usize,
+    pub cost: f32,
+}
+
+/// Instantiations of quantifier `from` that triggered instantiations of quantifier `to`.
+#[derive(Debug, Serialize)]
+pub struct QuantEdge {
+    pub from: String,
+    pub to: String,
+    /// number of pairs of instantiations of `from` and `to` where the second depends on the first
+    pub count: usize,
+}
+
+/// Instantiation graph aggregated by quantifier: cycles in this graph are the quantifiers behind matching loops.
+#[derive(Debug, Default, Serialize)]
+pub struct QuantGraph {
+    /// sorted by ID
+    pub nodes: Vec<QuantNode>,
+    /// sorted by source and target ID
+    pub edges: Vec<QuantEdge>,
+}
+
+impl QuantGraph {
+    /// Aggregates `dependencies` between `instantiations` by the quantifiers they instantiate, leaving out the
+    /// instantiations at the lines in `excluded`. Theory-solving and MBQI instantiations are grouped by their ID
+    /// (e.g. `arith#`) like quantifiers.
+    pub fn build(quantifiers: &TwoDMap<Quantifier>, instantiations: &BTreeMap<usize, Instantiation>, dependencies: &[Dependency], excluded: &HashSet<usize>) -> QuantGraph {
+        let mut nodes: BTreeMap<&str, QuantNode> = BTreeMap::new();
+        for inst in instantiations.values().filter(|inst| !excluded.contains(&inst.line_no)) {
+            let node = nodes.entry(&inst.quant_id).or_insert_with(|| QuantNode {
+                id: inst.quant_id.clone(),
+                name: quantifiers.get(&inst.quant_id).map_or_else(|| inst.quant_id.clone(), |quant| quant.name.clone()),
+                instances: 0,
+                cost: 0.0,
+            });
+            node.instances += 1;
+            node.cost += inst.cost;
+        }
+        // dependencies between the same two instantiations (e.g. with different blamed terms) are counted once
+        let mut seen = HashSet::new();
+        let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+        for dep in dependencies {
+            if !seen.insert((dep.from, dep.to)) {
+                continue;
+            }
+            let (Some(from), Some(to)) = (instantiations.get(&dep.from), instantiations.get(&dep.to)) else { continue };
+            if !excluded.contains(&dep.from) && !excluded.contains(&dep.to) {
+                *counts.entry((from.quant_id.as_str(), to.quant_id.as_str())).or_default() += 1;
+            }
+        }
+        let edges = counts.into_iter()
+            .map(|((from, to), count)| QuantEdge { from: from.to_string(), to: to.to_string(), count })
+            .collect();
+        QuantGraph { nodes: nodes.into_values().collect(), edges }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{test_logs::{self, parse}, Log};
+
+    #[test]
+    fn test_quant_graph() {
+        let mut parser = parse(Log::File(test_logs::SIMPLE.to_string()));
+        parser.update_costs();
+        let graph = parser.quant_graph(&HashSet::new());
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!((graph.nodes[0].id.as_str(), graph.nodes[0].name.as_str(), graph.nodes[0].instances), ("#5", "q1", 2));
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!((graph.edges[0].from.as_str(), graph.edges[0].to.as_str(), graph.edges[0].count), ("#5", "#5", 1));
+        let dot = crate::dot_output::get_quant_dot(&graph);
+        assert!(dot.contains("\t\"#5\" [ class=\"quant_5\", tooltip=\"#5\", label=\"q1\\n2 instances\\ncost 3.0\", width=0.94, height=0.62 ]"));
+        assert!(dot.contains("\t\"#5\" -> \"#5\" [ label=\"1\", tooltip=\"#5 -> #5: 1\", penwidth=1.0 ]"));
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::items::TwoDMap;
+use crate::parsers::z3parser1::Z3Parser1;
+
+/// number of items in a page if no limit is given
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+/// largest number of items in a page
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Which part of a list of items to return, and which of their fields.
+#[derive(Debug, Clone, Default)]
+pub struct ListQuery {
+    /// number of items to skip
+    pub offset: usize,
+    /// largest number of items to return (`DEFAULT_PAGE_SIZE` if 0, at most `MAX_PAGE_SIZE`)
+    pub limit: usize,
+    /// fields of each item to return (all if empty)
+    pub fields: Vec<String>,
+    /// only instantiations of the quantifier with this ID
+    pub quantifier: Option<String>,
+}
+
+/// An item in a page, with its ID (line number for instantiations).
+#[derive(Debug, Serialize)]
+pub struct Entry {
+    pub id: String,
+    pub data: Value,
+}
+
+/// Part of a list of items.
+#[derive(Debug, Serialize)]
+pub struct Page {
+    /// number of items in the whole list
+    pub total: usize,
+    pub offset: usize,
+    pub items: Vec<Entry>,
+}
+
+/// The instantiation at line `line_no`, with only `fields` (all if empty).
+/// # Errors
+/// Errors if a field does not exist.
+pub fn instantiation(parser: &Z3Parser1, line_no: usize, fields: &[String]) -> Result<Option<Value>, String> {
+    parser.instantiations().get(&line_no).map(|inst| select_fields(inst, fields)).transpose()
+}
+
+/// The term with ID `id` (`#` may be left out for terms without namespace), with only `fields` (all if empty).
+/// # Errors
+/// Errors if a field does not exist.
+pub fn term(parser: &Z3Parser1, id: &str, fields: &[String]) -> Result<Option<Value>, String> {
+    parser.terms().get(&full_id(id)).map(|term| select_fields(term, fields)).transpose()
+}
+
+/// The quantifier with ID `id` (`#` may be left out), with only `fields` (all if empty).
+/// # Errors
+/// Errors if a field does not exist.
+pub fn quantifier(parser: &Z3Parser1, id: &str, fields: &[String]) -> Result<Option<Value>, String> {
+    parser.quantifiers().get(&full_id(id)).map(|quant| select_fields(quant, fields)).transpose()
+}
+
+/// Page of instantiations, by line number.
+/// # Errors
+/// Errors if a field does not exist.
+pub fn instantiations(parser: &Z3Parser1, query: &ListQuery) -> Result<Page, String> {
+    let quantifier = query.quantifier.as_deref().map(full_id);
+    let items: Vec<_> = parser.instantiations().values()
+        .filter(|inst| quantifier.as_ref().is_none_or(|quant_id| inst.quant_id == *quant_id))
+        .map(|inst| (inst.line_no.to_string(), inst))
+        .collect();
+    page(items, query)
+}
+
+/// Page of terms, by namespace and ID.
+/// # Errors
+/// Errors if a field does not exist.
+pub fn terms(parser: &Z3Parser1, query: &ListQuery) -> Result<Page, String> {
+    page(sorted_entries(parser.terms()), query)
+}
+
+/// Page of quantifiers, by namespace and ID.
+/// # Errors
+/// Errors if a field does not exist.
+pub fn quantifiers(parser: &Z3Parser1, query: &ListQuery) -> Result<Page, String> {
+    page(sorted_entries(parser.quantifiers()), query)
+}
+
+/// `id` with a `#` in front if it has none.
+fn full_id(id: &str) -> String {
+    if id.contains('#') { id.to_string() } else { format!("#{}", id) }
+}
+
+/// Items of `map` with their full IDs, sorted by namespace and ID.
+fn sorted_entries<V>(map: &TwoDMap<V>) -> Vec<(String, &V)> {
+    let namespaces: BTreeMap<_, _> = map.0.iter().collect();
+    namespaces.into_iter()
+        .flat_map(|(namespace, items)| items.iter().map(move |(num, item)| (format!("{}#{}", namespace, num), item)))
+        .collect()
+}
+
+/// The part of `items` selected by `query`.
+fn page<T: Serialize>(items: Vec<(String, &T)>, query: &ListQuery) -> Result<Page, String> {
+    let limit = if query.limit == 0 { DEFAULT_PAGE_SIZE } else { query.limit.min(MAX_PAGE_SIZE) };
+    let total = items.len();
+    let items = items.into_iter()
+        .skip(query.offset)
+        .take(limit)
+        .map(|(id, item)| Ok(Entry { id, data: select_fields(item, &query.fields)? }))
+        .collect::<Result<_, String>>()?;
+    Ok(Page { total, offset: query.offset, items })
+}
+
+/// JSON representation of `item` with only `fields` (all if empty).
+/// # Errors
+/// Errors if `item` has no field of one of the names in `fields`.
+pub fn select_fields<T: Serialize>(item: &T, fields: &[String]) -> Result<Value, String> {
+    let value = serde_json::to_value(item).map_err(|e| e.to_string())?;
+    if fields.is_empty() {
+        return Ok(value);
+    }
+    let Value::Object(mut object) = value else {
+        return Err(String::from("Only objects have fields"));
+    };
+    let mut selected = serde_json::Map::new();
+    for field in fields {
+        let value = object.remove(field).ok_or_else(|| format!("Unknown field {}", field))?;
+        selected.insert(field.clone(), value);
+    }
+    Ok(Value::Object(selected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{test_logs, Log, Z3LogParser};
+
+    #[test]
+    fn test_queries() {
+        let mut parser = Z3Parser1::default();
+        parser.main_parse_loop(Log::File(test_logs::SIMPLE.to_string())).unwrap();
+        let fields = vec![String::from("quant_id"), String::from("z3_gen")];
+        assert_eq!(instantiation(&parser, 17, &fields).unwrap(), Some(serde_json::json!({ "quant_id": "#5", "z3_gen": 2 })));
+        assert_eq!(instantiation(&parser, 11, &[]).unwrap(), None);
+        assert!(instantiation(&parser, 17, &[String::from("nope")]).is_err());
+        assert_eq!(term(&parser, "1", &[String::from("name")]).unwrap(), Some(serde_json::json!({ "name": "a" })));
+        assert_eq!(quantifier(&parser, "#5", &[]).unwrap().unwrap()["name"], "q1");
+
+        let query = ListQuery { offset: 1, limit: 2, ..ListQuery::default() };
+        let page = terms(&parser, &query).unwrap();
+        assert_eq!(page.offset, 1);
+        assert_eq!(page.items.iter().map(|entry| entry.id.as_str()).collect::<Vec<_>>(), vec!["#2", "#3"]);
+        assert!(page.total > 3);
+        let query = ListQuery { quantifier: Some(String::from("5")), ..ListQuery::default() };
+        let page = instantiations(&parser, &query).unwrap();
+        assert_eq!((page.total, page.items[1].id.as_str()), (2, "17"));
+        let query = ListQuery { quantifier: Some(String::from("#6")), ..ListQuery::default() };
+        assert_eq!(instantiations(&parser, &query).unwrap().total, 0);
+        assert_eq!(quantifiers(&parser, &ListQuery::default()).unwrap().items[0].id, "#5");
+    }
+}
@@ -1,48 +1,205 @@
-use std::io::Write;
+use std::io::{Read, Write};
+use std::process::{Command, ExitStatus, Stdio, Child};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::file_io::open_file_truncate;
+use crate::file_io::{open_file_truncate, Renderer, Settings, LayoutEngine, GraphFormat};
+
+/// Pure-Rust layered graph layout and SVG output.
+pub(crate) mod native;
+pub use native::NativeRender;
+
+/// how often to check whether Graphviz has finished
+const GRAPHVIZ_POLL_INTERVAL: Duration = Duration::from_millis(20);
 
 /// Trait that supports rendering a Dot file as an SVG
 pub trait RenderSVG {
     /// Gets SVG output from given Dot file `input_file`, prints it to `output_file` and returns it.
-    fn make_svg(&self, input_file: &str, output_file: &str) -> String;
+    /// # Errors
+    /// Errors if the graph could not be rendered or the output could not be written.
+    fn make_svg(&self, input_file: &str, output_file: &str) -> Result<String, String>;
+
+    /// Renders given Dot file `input_file` in another format to `output_file`.
+    /// Only SVG is supported by default.
+    fn make_format(&self, input_file: &str, output_file: &str, format: &GraphFormat) -> Result<(), String> {
+        match format {
+            GraphFormat::Svg => self.make_svg(input_file, output_file).map(|_| ()),
+            _ => Err(format!("{:?} output is only supported when rendering with Graphviz", format)),
+        }
+    }
+}
+
+/// Renderer that calls Graphviz to create SVGs (and other formats)
+pub struct GraphVizRender {
+    /// Graphviz executable
+    pub program: String,
+    pub engine: LayoutEngine,
+    /// Rendering is stopped with an error if it takes longer than this
+    pub timeout: Option<Duration>,
+}
+
+impl Default for GraphVizRender {
+    fn default() -> Self {
+        GraphVizRender { program: String::from("dot"), engine: LayoutEngine::default(), timeout: None }
+    }
 }
 
-/// Renderer that calls Graphviz to create SVGs
-pub struct GraphVizRender;
 impl RenderSVG for GraphVizRender {
-    fn make_svg(&self, input_file: &str, output_file: &str) -> String {
-        use std::process::Command;
-        println!("Writing to dot");
-        let output = Command::new("dot")
-                    .arg("-Tsvg")
-                    .arg(input_file)
-                    .output()
-                    .expect("failed to execute process")
-                    .stdout;
-        let mut output_file = open_file_truncate(output_file);
-        output_file.write_all(&output).unwrap();
-        String::from_utf8_lossy(&output).to_string()
-    }
-}
-
-/// Modifies SVG output to add a link to CSS stylesheet.
-pub fn add_link_to_svg(input_file: &str, output_file: &str) {
-    use crate::file_io;
-    // hard-coded info for insertion
-    const INSERT_LINE_NO: usize = 9;
-    const INSERT_LINE: &str = "<link xmlns=\"http://www.w3.org/1999/xhtml\" rel=\"stylesheet\" href=\"styles.css\" type=\"text/css\" />\n";
-    let file_lines = file_io::read_lines(input_file).unwrap();
-    // open new file
-    let mut out_file = file_io::open_file_truncate(output_file);
-    for (line_no, line0) in file_lines.enumerate() {
-        // if correct line number
-        if line_no == INSERT_LINE_NO {
-            out_file.write_all(INSERT_LINE.as_bytes()).expect("failed to write line to new SVG");
+    fn make_svg(&self, input_file: &str, output_file: &str) -> Result<String, String> {
+        log::debug!("Rendering {} with Graphviz", input_file);
+        let output = self.run(input_file, &GraphFormat::Svg)?;
+        write_output(output_file, &output)?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    fn make_format(&self, input_file: &str, output_file: &str, format: &GraphFormat) -> Result<(), String> {
+        let output = self.run(input_file, format)?;
+        write_output(output_file, &output)
+    }
+}
+
+impl GraphVizRender {
+    /// Graphviz renderer configured by `settings`.
+    pub fn from_settings(settings: &Settings) -> GraphVizRender {
+        let timeout = if settings.render_timeout > 0.0 { Some(Duration::from_secs_f32(settings.render_timeout)) } else { None };
+        GraphVizRender { engine: settings.layout_engine.clone(), timeout, ..GraphVizRender::default() }
+    }
+
+    /// Returns true if the Graphviz executable can be run.
+    pub fn is_available(&self) -> bool {
+        Command::new(&self.program).arg("-V").output().is_ok_and(|output| output.status.success())
+    }
+
+    /// Runs Graphviz on `input_file` and returns its output in the given format.
+    /// Warnings printed by Graphviz are passed on; if it fails or times out, the error includes what it printed.
+    fn run(&self, input_file: &str, format: &GraphFormat) -> Result<Vec<u8>, String> {
+        let mut child = Command::new(&self.program)
+            .arg(format!("-K{}", self.engine.graphviz_name()))
+            .arg(format!("-T{}", format.graphviz_name()))
+            .arg(input_file)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run Graphviz ({}): {}", self.program, e))?;
+        // read both pipes concurrently so that Graphviz never blocks on a full pipe
+        let mut stdout = child.stdout.take().expect("stdout should be piped");
+        let mut stderr = child.stderr.take().expect("stderr should be piped");
+        let stdout_reader = thread::spawn(move || {
+            let mut buf = vec![];
+            stdout.read_to_end(&mut buf).map(|_| buf)
+        });
+        let stderr_reader = thread::spawn(move || {
+            let mut buf = String::new();
+            stderr.read_to_string(&mut buf).map(|_| buf)
+        });
+        let Some(status) = wait_with_timeout(&mut child, self.timeout)? else {
+            // the reader threads are left to finish on their own, in case the pipes are still held open
+            return Err(format!("Graphviz did not finish within {} seconds and was stopped", self.timeout.unwrap_or_default().as_secs_f32()));
+        };
+        let output = stdout_reader.join().expect("reader should not panic").map_err(|e| e.to_string())?;
+        let messages = stderr_reader.join().expect("reader should not panic").unwrap_or_default();
+        let messages = messages.trim();
+        if !status.success() {
+            return Err(format!("Graphviz failed ({}): {}", status, messages));
         }
-        // write line
-        let line = line0.unwrap() + "\n";
-        out_file.write_all(line.as_bytes()).expect("failed to write line to new SVG");
+        if !messages.is_empty() {
+            log::warn!("Graphviz: {}", messages);
+        }
+        Ok(output)
+    }
+}
+
+/// Waits for `child` to exit. If it is still running after `timeout`, kills it and returns `None`.
+pub(crate) fn wait_with_timeout(child: &mut Child, timeout: Option<Duration>) -> Result<Option<ExitStatus>, String> {
+    let Some(timeout) = timeout else {
+        return child.wait().map(Some).map_err(|e| e.to_string());
+    };
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            return Ok(Some(status));
+        }
+        if start.elapsed() >= timeout {
+            // ignore errors, the process may have exited in the meantime
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        thread::sleep(GRAPHVIZ_POLL_INTERVAL);
+    }
+}
+
+/// Writes rendered output to `filename`.
+pub(crate) fn write_output(filename: &str, output: &[u8]) -> Result<(), String> {
+    let mut output_file = open_file_truncate(filename);
+    output_file.write_all(output).and_then(|_| output_file.flush()).map_err(|e| format!("Error writing {}: {}", filename, e))
+}
+
+/// Returns the renderer chosen in settings, checking up front that Graphviz can be run if it is needed.
+/// `Renderer::Auto` uses Graphviz if it is available, and the native renderer otherwise.
+/// # Errors
+/// Errors if Graphviz was chosen explicitly but is not available.
+pub fn select_renderer(settings: &Settings) -> Result<Box<dyn RenderSVG>, String> {
+    let graphviz = GraphVizRender::from_settings(settings);
+    match settings.renderer {
+        Renderer::Graphviz => {
+            if graphviz.is_available() {
+                Ok(Box::new(graphviz))
+            } else {
+                Err(format!("Graphviz ({}) could not be run; install Graphviz or use the native renderer", graphviz.program))
+            }
+        },
+        Renderer::Native => Ok(Box::new(NativeRender)),
+        Renderer::Auto => {
+            if graphviz.is_available() {
+                Ok(Box::new(graphviz))
+            } else {
+                log::info!("Graphviz not found, using native renderer");
+                Ok(Box::new(NativeRender))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Writes a shell script standing in for Graphviz and returns a renderer that runs it.
+    fn fake_graphviz(name: &str, script: &str) -> GraphVizRender {
+        let path = std::env::temp_dir().join(format!("fake_dot_{}_{}", name, std::process::id()));
+        std::fs::write(&path, format!("#!/bin/sh\n{}", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        GraphVizRender { program: path.to_str().unwrap().to_string(), ..GraphVizRender::default() }
+    }
 
+    #[test]
+    fn test_graphviz_arguments() {
+        let mut render = fake_graphviz("args", "echo \"$@\"\n");
+        render.engine = LayoutEngine::Sfdp;
+        let output = render.run("graph.dot", &GraphFormat::Png).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "-Ksfdp -Tpng graph.dot\n");
+        assert!(render.is_available());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_graphviz_errors() {
+        let render = GraphVizRender { program: String::from("/nonexistent/dot"), ..GraphVizRender::default() };
+        assert!(!render.is_available());
+        assert!(render.run("graph.dot", &GraphFormat::Svg).unwrap_err().starts_with("Failed to run Graphviz"));
+        let render = fake_graphviz("fail", "echo 'Error: syntax error in line 3' >&2\nexit 1\n");
+        let error = render.run("graph.dot", &GraphFormat::Svg).unwrap_err();
+        assert!(error.contains("syntax error in line 3"), "{}", error);
+    }
+
+    #[test]
+    fn test_graphviz_timeout() {
+        let mut render = fake_graphviz("slow", "sleep 5\n");
+        render.timeout = Some(Duration::from_millis(100));
+        let start = Instant::now();
+        let error = render.run("graph.dot", &GraphFormat::Svg).unwrap_err();
+        assert!(error.contains("did not finish"), "{}", error);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,574 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+
+use super::RenderSVG;
+use crate::file_io::open_file_truncate;
+
+// layout constants, in SVG user units (points, like Graphviz)
+const NODE_HEIGHT: f64 = 36.0;
+const MIN_NODE_WIDTH: f64 = 54.0;
+const CHAR_WIDTH: f64 = 8.0;
+const NODE_SEP: f64 = 18.0;
+const RANK_SEP: f64 = 54.0;
+const MARGIN: f64 = 4.0;
+const ARROW_LENGTH: f64 = 10.0;
+const LINE_HEIGHT: f64 = 16.0;
+const POINTS_PER_INCH: f64 = 72.0;
+const SELF_LOOP_WIDTH: f64 = 24.0;
+/// number of up/down barycenter sweeps used to reduce edge crossings
+const ORDERING_SWEEPS: usize = 8;
+
+/// Renderer that lays out the instantiation graph itself (layered, Sugiyama-style) and writes the SVG directly,
+/// without needing Graphviz. Understands the subset of Dot produced by `dot_output`.
+pub struct NativeRender;
+
+impl RenderSVG for NativeRender {
+    fn make_svg(&self, input_file: &str, output_file: &str) -> Result<String, String> {
+        let dot = fs::read_to_string(input_file).map_err(|e| format!("Error reading {}: {}", input_file, e))?;
+        let svg = render_dot(&dot);
+        let mut output_file = open_file_truncate(output_file);
+        output_file.write_all(svg.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(svg)
+    }
+}
+
+/// Lays out and renders a Dot graph as SVG.
+pub fn render_dot(dot: &str) -> String {
+    let graph = DotGraph::parse(dot);
+    let layout = Layout::new(&graph);
+    layout.to_svg(&graph)
+}
+
+/// A node or edge statement's attributes, e.g. `[ class="quant_A", tooltip="quant_#A" ]`.
+type Attrs = BTreeMap<String, String>;
+
+/// A directed graph read from Dot text.
+#[derive(Debug, Default)]
+struct DotGraph {
+    name: String,
+    /// nodes in order of first appearance
+    nodes: Vec<(String, Attrs)>,
+    edges: Vec<(usize, usize, Attrs)>,
+}
+
+impl DotGraph {
+    /// Parses the statements of a `digraph`, one per line: `ID [ attrs ]` or `ID -> ID [ attrs ]`.
+    /// Nodes that only appear in edges are added without attributes.
+    fn parse(dot: &str) -> DotGraph {
+        let mut graph = DotGraph::default();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for line in dot.lines() {
+            let line = line.trim();
+            if let Some(header) = line.strip_prefix("digraph") {
+                graph.name = header.trim_end_matches('{').trim().trim_matches('"').to_string();
+                continue;
+            }
+            if line.is_empty() || line == "}" || line.starts_with("//") {
+                continue;
+            }
+            let (stmt, attrs) = match line.find('[') {
+                Some(i) => (line[..i].trim(), parse_attrs(&line[i..])),
+                None => (line.trim_end_matches(';').trim(), Attrs::new()),
+            };
+            if let Some((from, to)) = stmt.split_once("->") {
+                let from = graph.node_index(&mut index, unquote(from.trim()));
+                let to = graph.node_index(&mut index, unquote(to.trim()));
+                graph.edges.push((from, to, attrs));
+            } else if matches!(stmt, "node" | "edge" | "graph") || stmt.contains('=') {
+                // default attribute statements are not used by the native renderer
+                continue;
+            } else {
+                let i = graph.node_index(&mut index, unquote(stmt));
+                graph.nodes[i].1.extend(attrs);
+            }
+        }
+        graph
+    }
+
+    fn node_index(&mut self, index: &mut HashMap<String, usize>, id: &str) -> usize {
+        *index.entry(id.to_string()).or_insert_with(|| {
+            self.nodes.push((id.to_string(), Attrs::new()));
+            self.nodes.len() - 1
+        })
+    }
+}
+
+fn unquote(s: &str) -> &str {
+    s.strip_prefix('"').and_then(|s| s.strip_suffix('"')).unwrap_or(s)
+}
+
+/// Parses an attribute list `[ key="value", key2=value2 ]`.
+fn parse_attrs(s: &str) -> Attrs {
+    let mut attrs = Attrs::new();
+    let inner = s.trim().trim_end_matches(';').trim_start_matches('[').trim_end_matches(']');
+    let mut chars = inner.chars().peekable();
+    loop {
+        // key
+        let key: String = chars.by_ref()
+            .skip_while(|c| c.is_whitespace() || *c == ',' || *c == ';')
+            .take_while(|c| *c != '=')
+            .collect();
+        let key = key.trim().to_string();
+        if key.is_empty() {
+            break;
+        }
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        // value, quoted (with escapes) or bare
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some(escaped) = chars.next() {
+                            if escaped != '"' && escaped != '\\' {
+                                value.push('\\');
+                            }
+                            value.push(escaped);
+                        }
+                    }
+                    '"' => break,
+                    _ => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.peek() {
+                if *c == ',' || *c == ';' || c.is_whitespace() {
+                    break;
+                }
+                value.push(*c);
+                chars.next();
+            }
+        }
+        attrs.insert(key, value);
+    }
+    attrs
+}
+
+/// Escapes text for use in SVG/XML content and attribute values.
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Positions of all graph nodes and the routes of all edges.
+struct Layout {
+    /// center, width and height of each graph node
+    nodes: Vec<(f64, f64, f64, f64)>,
+    /// points of each edge (in the same order as the graph's edges)
+    edges: Vec<Vec<(f64, f64)>>,
+    width: f64,
+    height: f64,
+}
+
+impl Layout {
+    fn new(graph: &DotGraph) -> Layout {
+        let n = graph.nodes.len();
+        let sizes: Vec<(f64, f64)> = graph.nodes.iter().map(|(id, attrs)| node_size(id, attrs)).collect();
+
+        // 1. layer assignment: longest path from the sources, ignoring edges that would close a cycle
+        let forward = forward_edges(n, &graph.edges);
+        let mut successors: Vec<Vec<usize>> = vec![vec![]; n];
+        for &(from, to) in &forward {
+            successors[from].push(to);
+        }
+        let mut layer = vec![0; n];
+        for v in topological_order(n, &forward) {
+            for &w in &successors[v] {
+                layer[w] = layer[w].max(layer[v] + 1);
+            }
+        }
+
+        // 2. split long edges with dummy vertices so that every segment connects adjacent layers
+        // vertices 0..n are graph nodes, the rest are dummies
+        let mut vertex_layer = layer.clone();
+        let mut segments: Vec<(usize, usize)> = vec![];
+        let mut edge_chains: Vec<Vec<usize>> = vec![];
+        for &(from, to, _) in &graph.edges {
+            let (top, bottom) = if layer[from] <= layer[to] { (from, to) } else { (to, from) };
+            let mut chain = vec![top];
+            for l in layer[top] + 1..layer[bottom] {
+                vertex_layer.push(l);
+                chain.push(vertex_layer.len() - 1);
+            }
+            chain.push(bottom);
+            for pair in chain.windows(2) {
+                if pair[0] != pair[1] {
+                    segments.push((pair[0], pair[1]));
+                }
+            }
+            if top != from {
+                chain.reverse();
+            }
+            edge_chains.push(chain);
+        }
+        let num_vertices = vertex_layer.len();
+        let num_layers = vertex_layer.iter().max().map_or(0, |l| l + 1);
+        let mut up: Vec<Vec<usize>> = vec![vec![]; num_vertices];
+        let mut down: Vec<Vec<usize>> = vec![vec![]; num_vertices];
+        for &(a, b) in &segments {
+            if vertex_layer[a] + 1 == vertex_layer[b] {
+                down[a].push(b);
+                up[b].push(a);
+            }
+        }
+
+        // 3. ordering within layers: barycenter heuristic, alternating downward and upward sweeps
+        let mut layers: Vec<Vec<usize>> = vec![vec![]; num_layers];
+        for (v, &l) in vertex_layer.iter().enumerate() {
+            layers[l].push(v);
+        }
+        let mut pos = vec![0.0; num_vertices];
+        let update_pos = |layers: &Vec<Vec<usize>>, pos: &mut Vec<f64>| {
+            for layer in layers {
+                for (i, &v) in layer.iter().enumerate() {
+                    pos[v] = i as f64;
+                }
+            }
+        };
+        update_pos(&layers, &mut pos);
+        for sweep in 0..ORDERING_SWEEPS {
+            let downward = sweep % 2 == 0;
+            let order: Vec<usize> = if downward { (1..num_layers).collect() } else { (0..num_layers.saturating_sub(1)).rev().collect() };
+            for l in order {
+                let neighbours = if downward { &up } else { &down };
+                let mut keyed: Vec<(f64, usize)> = layers[l].iter()
+                    .map(|&v| (barycenter(&neighbours[v], &pos).unwrap_or(pos[v]), v))
+                    .collect();
+                keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+                layers[l] = keyed.into_iter().map(|(_, v)| v).collect();
+                for (i, &v) in layers[l].iter().enumerate() {
+                    pos[v] = i as f64;
+                }
+            }
+        }
+
+        // 4. coordinates: pack each layer, then move vertices towards their neighbours while keeping the order
+        let vertex_width = |v: usize| if v < n { sizes[v].0 } else { 0.0 };
+        let mut x = vec![0.0; num_vertices];
+        for layer in &layers {
+            let mut next = MARGIN;
+            for &v in layer {
+                x[v] = next + vertex_width(v) / 2.0;
+                next += vertex_width(v) + NODE_SEP;
+            }
+        }
+        for sweep in 0..ORDERING_SWEEPS {
+            let downward = sweep % 2 == 0;
+            let order: Vec<usize> = if downward { (1..num_layers).collect() } else { (0..num_layers.saturating_sub(1)).rev().collect() };
+            for l in order {
+                let neighbours = if downward { &up } else { &down };
+                let layer = &layers[l];
+                let desired: Vec<f64> = layer.iter().map(|&v| barycenter(&neighbours[v], &x).unwrap_or(x[v])).collect();
+                // place left to right at the desired position, without overlapping the previous vertex
+                let mut prev_right = f64::NEG_INFINITY;
+                for (i, &v) in layer.iter().enumerate() {
+                    let half = vertex_width(v) / 2.0;
+                    x[v] = desired[i].max(prev_right + NODE_SEP + half).max(MARGIN + half);
+                    prev_right = x[v] + half;
+                }
+            }
+        }
+        // self-loops are drawn to the right of their node
+        let loop_width = |v: usize| if v < n && graph.edges.iter().any(|&(from, to, _)| from == v && to == v) { SELF_LOOP_WIDTH } else { 0.0 };
+        let width = (0..num_vertices).map(|v| x[v] + vertex_width(v) / 2.0 + loop_width(v)).fold(0.0, f64::max) + MARGIN;
+        // each layer is as high as its highest node
+        let mut layer_heights = vec![NODE_HEIGHT; num_layers];
+        for v in 0..n {
+            layer_heights[vertex_layer[v]] = layer_heights[vertex_layer[v]].max(sizes[v].1);
+        }
+        let mut layer_centers = vec![];
+        let mut top = MARGIN;
+        for h in &layer_heights {
+            layer_centers.push(top + h / 2.0);
+            top += h + RANK_SEP;
+        }
+        let y = |v: usize| layer_centers[vertex_layer[v]];
+        let height = 2.0 * MARGIN + layer_heights.iter().sum::<f64>() + num_layers.saturating_sub(1) as f64 * RANK_SEP;
+
+        let nodes = (0..n).map(|v| (x[v], y(v), sizes[v].0, sizes[v].1)).collect();
+        let edges = edge_chains.iter().map(|chain| {
+            let mut points: Vec<(f64, f64)> = chain.iter().map(|&v| (x[v], y(v))).collect();
+            if let (Some(&first), Some(&last)) = (chain.first(), chain.last()) {
+                // start and end at the boundary of the ellipses
+                if points.len() >= 2 && first != last {
+                    let len = points.len();
+                    points[0] = ellipse_boundary(points[0], points[1], sizes[first].0 / 2.0, sizes[first].1 / 2.0);
+                    points[len - 1] = ellipse_boundary(points[len - 1], points[len - 2], sizes[last].0 / 2.0, sizes[last].1 / 2.0);
+                } else if first == last {
+                    let (cx, cy, (w, h)) = (x[first], y(first), sizes[first]);
+                    let right = cx + w / 2.0;
+                    points = vec![
+                        (right - 2.0, cy - h / 4.0),
+                        (right + SELF_LOOP_WIDTH - MARGIN, cy - h / 4.0),
+                        (right + SELF_LOOP_WIDTH - MARGIN, cy + h / 4.0),
+                        (right - 2.0, cy + h / 4.0),
+                    ];
+                }
+            }
+            points
+        }).collect();
+        Layout { nodes, edges, width, height }
+    }
+
+    fn to_svg(&self, graph: &DotGraph) -> String {
+        let name = escape_xml(&graph.name);
+        // the header has the same line structure as Graphviz's output, so the same post-processing applies
+        let mut svg = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+        svg += "<!DOCTYPE svg PUBLIC \"-//W3C//DTD SVG 1.1//EN\"\n";
+        svg += " \"http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd\">\n";
+        svg += &format!("<!-- Generated by {} native renderer -->\n", env!("CARGO_PKG_NAME"));
+        svg += &format!("<!-- Title: {} -->\n", name);
+        svg += &format!("<svg width=\"{w:.0}pt\" height=\"{h:.0}pt\"\n viewBox=\"0.00 0.00 {w:.2} {h:.2}\" xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\">\n",
+            w = self.width, h = self.height);
+        svg += "<g id=\"graph0\" class=\"graph\">\n";
+        svg += &format!("<title>{}</title>\n", name);
+        svg += &format!("<polygon fill=\"white\" stroke=\"none\" points=\"0,0 {w:.2},0 {w:.2},{h:.2} 0,{h:.2} 0,0\"/>\n", w = self.width, h = self.height);
+        for (i, ((from, to, attrs), points)) in graph.edges.iter().zip(&self.edges).enumerate() {
+            let title = format!("{}&#45;&gt;{}", escape_xml(&graph.nodes[*from].0), escape_xml(&graph.nodes[*to].0));
+            svg += &self.edge_svg(i + 1, &title, attrs, points);
+        }
+        for (i, ((id, attrs), &(cx, cy, width, height))) in graph.nodes.iter().zip(&self.nodes).enumerate() {
+            let class = attrs.get("class").map(|c| format!("node {}", escape_xml(c))).unwrap_or_else(|| String::from("node"));
+            let group_id = attrs.get("id").map_or_else(|| format!("node{}", i + 1), |id| escape_xml(id));
+            let fill = attrs.get("fillcolor").map_or("none", String::as_str);
+            svg += &format!("<g id=\"{}\" class=\"{}\">\n<title>{}</title>\n", group_id, class, escape_xml(id));
+            let (link_start, link_end) = link(&group_id, attrs);
+            svg += &link_start;
+            svg += &format!("<ellipse fill=\"{}\" stroke=\"black\"{} cx=\"{:.2}\" cy=\"{:.2}\" rx=\"{:.2}\" ry=\"{:.2}\"/>\n",
+                escape_xml(fill), stroke_width(attrs), cx, cy, width / 2.0, height / 2.0);
+            let lines = label_lines(attrs.get("label").unwrap_or(id));
+            for (k, line) in lines.iter().enumerate() {
+                let offset = (k as f64 - (lines.len() - 1) as f64 / 2.0) * LINE_HEIGHT;
+                svg += &format!("<text text-anchor=\"middle\" x=\"{:.2}\" y=\"{:.2}\" font-family=\"Times,serif\" font-size=\"14.00\">{}</text>\n",
+                    cx, cy + 4.5 + offset, escape_xml(line));
+            }
+            svg += &link_end;
+            svg += "</g>\n";
+        }
+        svg += "</g>\n</svg>\n";
+        svg
+    }
+
+    fn edge_svg(&self, index: usize, title: &str, attrs: &Attrs, points: &[(f64, f64)]) -> String {
+        let group_id = format!("edge{}", index);
+        let mut result = format!("<g id=\"{}\" class=\"edge\">\n<title>{}</title>\n", group_id, title);
+        if points.len() < 2 {
+            return result + "</g>\n";
+        }
+        let (link_start, link_end) = link(&group_id, attrs);
+        result += &link_start;
+        let dash = match attrs.get("style").map(String::as_str) {
+            Some("dashed") => " stroke-dasharray=\"5,2\"",
+            Some("dotted") => " stroke-dasharray=\"1,3\"",
+            _ => "",
+        };
+        let color = escape_xml(attrs.get("color").map_or("black", String::as_str));
+        // shorten the last segment so that the arrowhead ends at the target
+        let mut line = points.to_vec();
+        let (tip, before) = (points[points.len() - 1], points[points.len() - 2]);
+        let (dx, dy) = (tip.0 - before.0, tip.1 - before.1);
+        let len = (dx * dx + dy * dy).sqrt().max(f64::EPSILON);
+        let (ux, uy) = (dx / len, dy / len);
+        let base = (tip.0 - ux * ARROW_LENGTH, tip.1 - uy * ARROW_LENGTH);
+        let last = line.len() - 1;
+        line[last] = base;
+        let path: Vec<String> = line.iter().map(|(x, y)| format!("{:.2},{:.2}", x, y)).collect();
+        result += &format!("<path fill=\"none\" stroke=\"{}\"{}{} d=\"M{}\"/>\n", color, stroke_width(attrs), dash, path.join(" L"));
+        let (px, py) = (-uy * ARROW_LENGTH / 3.0, ux * ARROW_LENGTH / 3.0);
+        result += &format!("<polygon fill=\"{c}\" stroke=\"{c}\" points=\"{:.2},{:.2} {:.2},{:.2} {:.2},{:.2} {:.2},{:.2}\"/>\n",
+            base.0 + px, base.1 + py, tip.0, tip.1, base.0 - px, base.1 - py, base.0 + px, base.1 + py, c = color);
+        result + &link_end + "</g>\n"
+    }
+}
+
+/// Start and end of a link around the shapes of the node or edge with group ID `group_id`,
+/// if it has a `URL` or `tooltip` (shown by browsers as the link title), like Graphviz does.
+fn link(group_id: &str, attrs: &Attrs) -> (String, String) {
+    let href = attrs.get("URL").map(|url| format!(" xlink:href=\"{}\"", escape_xml(url)));
+    let title = attrs.get("tooltip").map(|tooltip| format!(" xlink:title=\"{}\"", escape_xml(tooltip).replace("\\n", "&#10;")));
+    if href.is_none() && title.is_none() {
+        return (String::new(), String::new());
+    }
+    let start = format!("<g id=\"a_{}\"><a{}{}>\n", group_id, href.unwrap_or_default(), title.unwrap_or_default());
+    (start, String::from("</a>\n</g>\n"))
+}
+
+/// SVG stroke width attribute for the `penwidth` attribute, if it is set.
+fn stroke_width(attrs: &Attrs) -> String {
+    attrs.get("penwidth").map(|width| format!(" stroke-width=\"{}\"", escape_xml(width))).unwrap_or_default()
+}
+
+/// Lines of a label, separated by Dot line breaks (`\n`).
+fn label_lines(label: &str) -> Vec<&str> {
+    label.split("\\n").collect()
+}
+
+/// Width and height of a node: large enough for its label, and at least the `width` and `height` attributes (in inches).
+fn node_size(id: &str, attrs: &Attrs) -> (f64, f64) {
+    let lines = label_lines(attrs.get("label").map_or(id, String::as_str));
+    let width = lines.iter().map(|line| node_width(line)).fold(0.0, f64::max);
+    let height = NODE_HEIGHT.max(lines.len() as f64 * LINE_HEIGHT + NODE_SEP);
+    let inches = |key: &str| attrs.get(key).and_then(|value| value.parse::<f64>().ok()).map_or(0.0, |value| value * POINTS_PER_INCH);
+    (width.max(inches("width")), height.max(inches("height")))
+}
+
+/// Width of a node with the given label.
+fn node_width(label: &str) -> f64 {
+    MIN_NODE_WIDTH.max(label.chars().count() as f64 * CHAR_WIDTH + 2.0 * NODE_SEP)
+}
+
+/// Average position of `vertices`, if there are any.
+fn barycenter(vertices: &[usize], pos: &[f64]) -> Option<f64> {
+    if vertices.is_empty() {
+        None
+    } else {
+        Some(vertices.iter().map(|&v| pos[v]).sum::<f64>() / vertices.len() as f64)
+    }
+}
+
+/// Point where the line from the center of an ellipse towards `towards` leaves the ellipse.
+fn ellipse_boundary(center: (f64, f64), towards: (f64, f64), rx: f64, ry: f64) -> (f64, f64) {
+    let (dx, dy) = (towards.0 - center.0, towards.1 - center.1);
+    let t = 1.0 / ((dx / rx).powi(2) + (dy / ry).powi(2)).sqrt();
+    if t.is_finite() && t < 1.0 {
+        (center.0 + dx * t, center.1 + dy * t)
+    } else {
+        center
+    }
+}
+
+/// Edges of the graph that do not close a cycle (found by depth-first search), without self-loops.
+fn forward_edges(n: usize, edges: &[(usize, usize, Attrs)]) -> Vec<(usize, usize)> {
+    let mut adjacent: Vec<Vec<usize>> = vec![vec![]; n];
+    for &(from, to, _) in edges {
+        if from != to {
+            adjacent[from].push(to);
+        }
+    }
+    // 0 = unvisited, 1 = on the DFS stack, 2 = finished
+    let mut state = vec![0u8; n];
+    let mut result = vec![];
+    for root in 0..n {
+        if state[root] != 0 {
+            continue;
+        }
+        let mut stack = vec![(root, 0)];
+        state[root] = 1;
+        while let Some((v, i)) = stack.pop() {
+            if let Some(&w) = adjacent[v].get(i) {
+                stack.push((v, i + 1));
+                match state[w] {
+                    0 => {
+                        result.push((v, w));
+                        state[w] = 1;
+                        stack.push((w, 0));
+                    }
+                    2 => result.push((v, w)),
+                    _ => {} // back edge
+                }
+            } else {
+                state[v] = 2;
+            }
+        }
+    }
+    result
+}
+
+/// Topological order of the nodes of an acyclic graph (Kahn's algorithm, preferring earlier nodes).
+fn topological_order(n: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut in_degree = vec![0; n];
+    let mut adjacent: Vec<Vec<usize>> = vec![vec![]; n];
+    for &(from, to) in edges {
+        in_degree[to] += 1;
+        adjacent[from].push(to);
+    }
+    let mut ready: std::collections::BTreeSet<usize> = (0..n).filter(|&v| in_degree[v] == 0).collect();
+    let mut order = vec![];
+    while let Some(v) = ready.pop_first() {
+        order.push(v);
+        for &w in &adjacent[v] {
+            in_degree[w] -= 1;
+            if in_degree[w] == 0 {
+                ready.insert(w);
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOT: &str = r#"digraph test {
+	1 [ class="quant_A", tooltip="quant_#A" ]
+	2 [ class="quant_nsB", tooltip="quant_ns#B" ]
+	3 [ class="quant_A", tooltip="quant_#A" ]
+	1 -> 2 [ ]
+	2 -> 3 [ ]
+	1 -> 3 [ ]
+}
+"#;
+
+    #[test]
+    fn test_parse_dot() {
+        let graph = DotGraph::parse(DOT);
+        assert_eq!(graph.name, "test");
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.nodes[1].1.get("tooltip").unwrap(), "quant_ns#B");
+        assert_eq!(graph.edges.iter().map(|(a, b, _)| (*a, *b)).collect::<Vec<_>>(), vec![(0, 1), (1, 2), (0, 2)]);
+        let attrs = parse_attrs(r#"[ label="a \"b\"", style=dashed ]"#);
+        assert_eq!(attrs.get("label").unwrap(), "a \"b\"");
+        assert_eq!(attrs.get("style").unwrap(), "dashed");
+    }
+
+    #[test]
+    fn test_layout_is_layered() {
+        let graph = DotGraph::parse(DOT);
+        let layout = Layout::new(&graph);
+        // each edge points downwards, and the long edge 1 -> 3 is routed through a dummy vertex
+        for ((from, to, _), points) in graph.edges.iter().zip(&layout.edges) {
+            assert!(layout.nodes[*from].1 < layout.nodes[*to].1);
+            assert!(points.windows(2).all(|p| p[0].1 < p[1].1));
+        }
+        assert_eq!(layout.edges[2].len(), 3);
+        let svg = layout.to_svg(&graph);
+        assert!(svg.contains("class=\"node quant_nsB\""));
+        assert_eq!(svg.matches("<ellipse").count(), 3);
+        assert_eq!(svg.matches("class=\"edge\"").count(), 3);
+    }
+
+    #[test]
+    fn test_cycles_and_empty_graphs() {
+        let svg = render_dot("digraph g {\n\ta -> b [ ]\n\tb -> a [ ]\n\ta -> a [ ]\n}\n");
+        assert_eq!(svg.matches("<ellipse").count(), 2);
+        // the self-loop is drawn beside its node
+        let graph = DotGraph::parse("digraph g {\n\t\"#5\" -> \"#5\" [ ]\n}\n");
+        let layout = Layout::new(&graph);
+        assert_eq!(graph.nodes[0].0, "#5");
+        assert_eq!(layout.edges[0].len(), 4);
+        assert!(layout.edges[0].iter().all(|p| p.0 > layout.nodes[0].0 && p.0 < layout.width));
+        let svg = render_dot("digraph empty {\n}\n");
+        assert!(svg.contains("<svg") && !svg.contains("<ellipse"));
+    }
+
+    #[test]
+    fn test_labels_and_links() {
+        let dot = "digraph g {\n\t1 [ id=\"inst-1\", URL=\"#inst-1\", tooltip=\"quant_#A\", label=\"@1\\nq\\ncost 1.0\", height=1.5 ]\n\t2 [ ]\n\t1 -> 2 [ style=dotted, tooltip=\"a\\nb\" ]\n}\n";
+        let graph = DotGraph::parse(dot);
+        let layout = Layout::new(&graph);
+        assert_eq!(layout.nodes[0].3, 1.5 * POINTS_PER_INCH);
+        assert_eq!(layout.nodes[1].3, NODE_HEIGHT);
+        let svg = layout.to_svg(&graph);
+        assert!(svg.contains("<g id=\"inst-1\" class=\"node\">\n<title>1</title>\n<g id=\"a_inst-1\"><a xlink:href=\"#inst-1\" xlink:title=\"quant_#A\">"));
+        assert!(svg.contains(">@1</text>") && svg.contains(">q</text>") && svg.contains(">cost 1.0</text>"));
+        assert!(svg.contains("<g id=\"a_edge1\"><a xlink:title=\"a&#10;b\">"));
+        assert!(svg.contains("stroke-dasharray=\"1,3\""));
+    }
+}
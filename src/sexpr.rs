@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+/// Lines (1-based) an s-expression starts and ends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lines {
+    pub first: usize,
+    pub last: usize,
+}
+
+/// An s-expression of SMT-LIB text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SExpr {
+    Atom(String),
+    List(Vec<SExpr>, Lines),
+}
+
+/// The parts of a quantifier `(forall ((x Int) ...) (! body :qid name :pattern (...)))` (or `exists`).
+pub struct QuantExpr<'a> {
+    /// variables and their sorts
+    pub vars: Vec<(String, String)>,
+    /// body without its `!` annotations
+    pub body: &'a SExpr,
+    /// `:qid` attribute, if any
+    pub qid: Option<String>,
+    /// `:pattern` attributes, each a list of terms
+    pub patterns: Vec<&'a SExpr>,
+}
+
+impl SExpr {
+    pub fn atom(&self) -> Option<&str> {
+        match self {
+            SExpr::Atom(atom) => Some(atom),
+            SExpr::List(..) => None,
+        }
+    }
+
+    /// SMT-LIB text of the expression, on one line.
+    pub fn text(&self) -> String {
+        match self {
+            SExpr::Atom(atom) => atom.clone(),
+            SExpr::List(items, _) => {
+                let items: Vec<String> = items.iter().map(SExpr::text).collect();
+                format!("({})", items.join(" "))
+            }
+        }
+    }
+
+    /// The expression with the variables in `bindings` replaced by their values.
+    /// Variables shadowed by binders inside the expression are not told apart.
+    pub fn substitute(&self, bindings: &HashMap<&str, &SExpr>) -> SExpr {
+        match self {
+            SExpr::Atom(atom) => bindings.get(atom.as_str()).map_or_else(|| self.clone(), |value| (*value).clone()),
+            SExpr::List(items, lines) => SExpr::List(items.iter().map(|item| item.substitute(bindings)).collect(), *lines),
+        }
+    }
+
+    /// The parts of the expression if it is a `forall` or `exists` quantifier.
+    pub fn as_quantifier(&self) -> Option<QuantExpr<'_>> {
+        let SExpr::List(items, _) = self else { return None };
+        let [binder, SExpr::List(bindings, _), formula] = &items[..] else { return None };
+        if !matches!(binder.atom(), Some("forall" | "exists")) {
+            return None;
+        }
+        let vars = bindings.iter()
+            .filter_map(|binding| match binding {
+                SExpr::List(var, _) if var.len() == 2 => Some((var[0].text(), var[1].text())),
+                _ => None,
+            })
+            .collect();
+        let (body, attributes) = match formula {
+            SExpr::List(annotated, _) if annotated.len() >= 2 && annotated[0].atom() == Some("!") => (&annotated[1], &annotated[2..]),
+            _ => (formula, &[][..]),
+        };
+        let mut quant = QuantExpr { vars, body, qid: None, patterns: vec![] };
+        for pair in attributes.windows(2) {
+            match pair[0].atom() {
+                Some(":qid") => quant.qid = Some(pair[1].text()),
+                Some(":pattern") => quant.patterns.push(&pair[1]),
+                _ => {},
+            }
+        }
+        Some(quant)
+    }
+}
+
+/// Reads all s-expressions of `text`, skipping `;` comments.
+/// Quoted symbols (`|...|`) and string literals are read as single atoms.
+/// # Errors
+/// Errors if parentheses or quotes are unbalanced.
+pub fn read_sexprs(text: &str) -> Result<Vec<SExpr>, String> {
+    let mut open: Vec<(Vec<SExpr>, usize)> = vec![];
+    let mut done = vec![];
+    let mut chars = text.chars().peekable();
+    let mut line_no = 1;
+    while let Some(c) = chars.next() {
+        let expr = match c {
+            '\n' => {
+                line_no += 1;
+                continue;
+            },
+            ';' => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+                continue;
+            },
+            '(' => {
+                open.push((vec![], line_no));
+                continue;
+            },
+            ')' => {
+                let (items, first) = open.pop().ok_or_else(|| format!("Unexpected ) on line {}", line_no))?;
+                SExpr::List(items, Lines { first, last: line_no })
+            },
+            _ if c.is_whitespace() => continue,
+            '|' | '"' => {
+                let start = line_no;
+                let mut atom = String::from(c);
+                loop {
+                    let next = chars.next().ok_or_else(|| format!("Unterminated {} starting on line {}", c, start))?;
+                    atom.push(next);
+                    if next == '\n' {
+                        line_no += 1;
+                    } else if next == c {
+                        // "" is an escaped quote inside a string literal
+                        if c == '"' && chars.next_if_eq(&'"').is_some() {
+                            atom.push('"');
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                SExpr::Atom(atom)
+            },
+            _ => {
+                let mut atom = String::from(c);
+                while let Some(next) = chars.next_if(|c| !c.is_whitespace() && !"();|\"".contains(*c)) {
+                    atom.push(next);
+                }
+                SExpr::Atom(atom)
+            },
+        };
+        match open.last_mut() {
+            Some((items, _)) => items.push(expr),
+            None => done.push(expr),
+        }
+    }
+    match open.last() {
+        Some((_, start)) => Err(format!("Unclosed ( from line {}", start)),
+        None => Ok(done),
+    }
+}
@@ -0,0 +1,161 @@
+use serde::Serialize;
+
+use crate::sexpr::{read_sexprs, SExpr};
+
+/// longest source snippet shown, in characters
+const MAX_SNIPPET_LEN: usize = 200;
+
+/// A quantifier written in an `assert` of an SMT2 file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourceQuantifier {
+    /// `:qid` attribute (without `|` quotes), if any
+    pub qid: Option<String>,
+    /// line the quantifier starts on (1-based)
+    pub line: usize,
+    /// line the quantifier ends on
+    pub last_line: usize,
+    /// the quantifier's text, on one line
+    pub text: String,
+    /// the terms of each `:pattern` attribute
+    pub triggers: Vec<String>,
+}
+
+impl SourceQuantifier {
+    /// `<file>:<line>: <snippet>`, followed by a line listing the triggers if there are any.
+    pub fn describe(&self, file: &str) -> String {
+        let mut snippet: String = self.text.chars().take(MAX_SNIPPET_LEN).collect();
+        if snippet.len() < self.text.len() {
+            snippet += "…";
+        }
+        let mut result = format!("{}:{}: {}", file, self.line, snippet);
+        if !self.triggers.is_empty() {
+            result += &format!("\ntriggers: {}", self.triggers.join(" | "));
+        }
+        result
+    }
+}
+
+/// Quantifiers of an SMT2 file, for finding where the quantifiers of a log come from.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    /// name of the SMT2 file
+    pub file: String,
+    quantifiers: Vec<SourceQuantifier>,
+}
+
+impl SourceMap {
+    /// Reads the quantifiers of the SMT2 file `filename`.
+    /// # Errors
+    /// Errors if the file cannot be read or is not made of s-expressions.
+    pub fn from_file(filename: &str) -> Result<SourceMap, String> {
+        let text = std::fs::read_to_string(filename).map_err(|e| format!("Error reading {}: {}", filename, e))?;
+        SourceMap::parse(filename, &text).map_err(|e| format!("Error parsing {}: {}", filename, e))
+    }
+
+    /// Reads the quantifiers of the `assert` commands in `text`, the contents of the SMT2 file `file`, including nested ones.
+    /// # Errors
+    /// Errors if `text` is not made of s-expressions.
+    pub fn parse(file: &str, text: &str) -> Result<SourceMap, String> {
+        let mut quantifiers = vec![];
+        for command in read_sexprs(text)? {
+            if let SExpr::List(items, _) = &command {
+                if let [assert, formula] = &items[..] {
+                    if assert.atom() == Some("assert") {
+                        add_quantifiers(formula, &mut quantifiers);
+                    }
+                }
+            }
+        }
+        Ok(SourceMap { file: file.to_string(), quantifiers })
+    }
+
+    /// All quantifiers, in the order they appear in the file.
+    pub fn quantifiers(&self) -> &[SourceQuantifier] {
+        &self.quantifiers
+    }
+
+    /// The quantifier logged with the name `name`: the one with that `:qid`, or, for Z3's names of quantifiers without one
+    /// (`k!<line>`, where the line is in the quantifier), the innermost quantifier without a `:qid` around that line.
+    pub fn find(&self, name: &str) -> Option<&SourceQuantifier> {
+        let name = name.trim_matches('|');
+        if let Some(quant) = self.quantifiers.iter().find(|quant| quant.qid.as_deref() == Some(name)) {
+            return Some(quant);
+        }
+        let line: usize = name.strip_prefix("k!")?.parse().ok()?;
+        self.quantifiers.iter()
+            .filter(|quant| quant.qid.is_none() && quant.line <= line && line <= quant.last_line)
+            .max_by_key(|quant| quant.line)
+    }
+
+    /// `SourceQuantifier::describe` for the quantifier logged with the name `name`, empty if it is not found.
+    pub fn describe(&self, name: &str) -> String {
+        self.find(name).map_or_else(String::new, |quant| quant.describe(&self.file))
+    }
+}
+
+/// Adds the quantifiers in `expr` to `quantifiers`, outer ones first.
+fn add_quantifiers(expr: &SExpr, quantifiers: &mut Vec<SourceQuantifier>) {
+    let SExpr::List(items, lines) = expr else { return };
+    if let Some(quant) = expr.as_quantifier() {
+        quantifiers.push(SourceQuantifier {
+            qid: quant.qid.map(|qid| qid.trim_matches('|').to_string()),
+            line: lines.first,
+            last_line: lines.last,
+            text: expr.text(),
+            triggers: quant.patterns.iter().map(|pattern| match pattern {
+                SExpr::List(terms, _) => terms.iter().map(SExpr::text).collect::<Vec<_>>().join(", "),
+                SExpr::Atom(atom) => atom.clone(),
+            }).collect(),
+        });
+    }
+    for item in items {
+        add_quantifiers(item, quantifiers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_io::Settings;
+    use crate::parsers::{test_logs::{self, parse}, Log};
+
+    const SMT2: &str = "(declare-fun f (Int) Int)
+(assert (forall ((x Int))
+    (! (> (f x) 0) :qid |prog.l2| :pattern ((f x)))))
+; a comment with (unbalanced parentheses
+(assert (forall ((x Int) (y Int))
+    (=> (> x y)
+        (exists ((z Int)) (= (f z) x)))))
+(check-sat)
+";
+
+    #[test]
+    fn test_source_map() {
+        let map = SourceMap::parse("in.smt2", SMT2).unwrap();
+        assert_eq!(map.quantifiers().iter().map(|quant| (quant.line, quant.last_line)).collect::<Vec<_>>(), vec![(2, 3), (5, 7), (7, 7)]);
+        let quant = map.find("prog.l2").unwrap();
+        assert_eq!(quant.triggers, vec!["(f x)"]);
+        assert_eq!(map.describe("|prog.l2|"),
+            "in.smt2:2: (forall ((x Int)) (! (> (f x) 0) :qid |prog.l2| :pattern ((f x))))\ntriggers: (f x)");
+        // Z3's names for quantifiers without a qid
+        assert_eq!(map.find("k!6").unwrap().line, 5);
+        assert_eq!(map.find("k!7").unwrap().text, "(exists ((z Int)) (= (f z) x))");
+        assert_eq!(map.find("k!9"), None);
+        assert_eq!(map.describe("other"), "");
+    }
+
+    #[test]
+    fn test_sources_are_read_once() {
+        let filename = std::env::temp_dir().join(format!("sources_{}.smt2", std::process::id())).to_str().unwrap().to_string();
+        std::fs::write(&filename, "(assert (forall ((x Int)) (! (> x 0) :qid q1)))\n").unwrap();
+        let settings = Settings { smt2_file: filename.clone(), ..Settings::default() };
+        let mut parser = parse(Log::File(test_logs::SIMPLE.to_string()));
+        parser.load_sources(&settings);
+        std::fs::remove_file(&filename).unwrap();
+        // a later save (e.g. a refresh in follow mode) keeps the sources read before
+        parser.load_sources(&settings);
+        assert_eq!(parser.sources().and_then(|sources| sources.find("q1")).map(|quant| quant.line), Some(1));
+        parser.load_sources(&Settings::default());
+        assert!(parser.sources().is_none());
+    }
+}
@@ -0,0 +1,172 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use crate::file_io::Settings;
+use crate::render::wait_with_timeout;
+
+/// trace written by the solver
+pub const OUT_TRACE: &str = "out/trace.log";
+/// solver run if `settings.solver_command` is empty
+const DEFAULT_SOLVER_COMMAND: &str = "z3";
+
+/// Whether `filename` is an SMT-LIB 2 input file to run a solver on, rather than a log.
+pub fn is_smt2_file(filename: &str) -> bool {
+    filename.ends_with(".smt2")
+}
+
+/// Runs Z3 (or a solver taking the same options) on an SMT2 file so that it writes a trace log for `Z3Parser1`.
+#[derive(Debug, Clone)]
+pub struct SolverRunner {
+    /// Solver executable
+    pub program: String,
+    /// Arguments passed before the tracing options and the input file
+    pub args: Vec<String>,
+    /// Where the solver writes the trace
+    pub trace_file: String,
+    /// The solver is stopped with an error if it takes longer than this
+    pub timeout: Option<Duration>,
+}
+
+/// What a solver run printed and where its trace is.
+#[derive(Debug)]
+pub struct SolverRun {
+    /// first line printed, e.g. `sat`, `unsat` or `unknown` (empty if nothing was printed)
+    pub result: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// exit code, `None` if the solver was killed by a signal
+    pub exit_code: Option<i32>,
+    pub trace_file: String,
+}
+
+impl SolverRunner {
+    /// Runner for `settings.solver_command` with `settings.solver_args`, writing the trace to `OUT_TRACE`.
+    pub fn from_settings(settings: &Settings) -> SolverRunner {
+        let program = if settings.solver_command.is_empty() { DEFAULT_SOLVER_COMMAND } else { &settings.solver_command };
+        let timeout = if settings.solver_timeout > 0.0 { Some(Duration::from_secs_f32(settings.solver_timeout)) } else { None };
+        SolverRunner { program: program.to_string(), args: settings.solver_args.clone(), trace_file: OUT_TRACE.to_string(), timeout }
+    }
+
+    /// Options that make Z3 log instantiations, with the proof terms needed for equality explanations, to `trace_file`.
+    pub fn trace_args(&self) -> Vec<String> {
+        vec![String::from("trace=true"), String::from("proof=true"), format!("trace_file_name={}", self.trace_file)]
+    }
+
+    /// Runs the solver on `smt2_file` and waits for it to finish. Messages on standard error are passed on as warnings,
+    /// as is a failing exit code, since the trace up to the failure can still be parsed.
+    /// # Errors
+    /// Errors if the directory of `trace_file` cannot be created, or the solver cannot be started,
+    /// does not finish within `timeout`, or does not write a trace.
+    pub fn run(&self, smt2_file: &str) -> Result<SolverRun, String> {
+        if let Some(dir) = Path::new(&self.trace_file).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("Error creating {}: {}", dir.display(), e))?;
+        }
+        // a trace left over from an earlier run must not be mistaken for this one's
+        let _ = fs::remove_file(&self.trace_file);
+        log::info!("Running {} on {}", self.program, smt2_file);
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .args(self.trace_args())
+            .arg(smt2_file)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run the solver ({}): {}", self.program, e))?;
+        // read both pipes concurrently so that the solver never blocks on a full pipe
+        let mut stdout = child.stdout.take().expect("stdout should be piped");
+        let mut stderr = child.stderr.take().expect("stderr should be piped");
+        let stdout_reader = thread::spawn(move || {
+            let mut buf = String::new();
+            stdout.read_to_string(&mut buf).map(|_| buf)
+        });
+        let stderr_reader = thread::spawn(move || {
+            let mut buf = String::new();
+            stderr.read_to_string(&mut buf).map(|_| buf)
+        });
+        let Some(status) = wait_with_timeout(&mut child, self.timeout)? else {
+            return Err(format!("The solver did not finish within {} seconds and was stopped", self.timeout.unwrap_or_default().as_secs_f32()));
+        };
+        let stdout = stdout_reader.join().expect("reader should not panic").map_err(|e| e.to_string())?;
+        let stderr = stderr_reader.join().expect("reader should not panic").unwrap_or_default();
+        if !stderr.trim().is_empty() {
+            log::warn!("{}: {}", self.program, stderr.trim());
+        }
+        if !status.success() {
+            log::warn!("{} failed ({}): {}", self.program, status, stdout.trim());
+        }
+        if !fs::exists(&self.trace_file).unwrap_or(false) {
+            return Err(format!("{} wrote no trace to {}; does it accept Z3's trace options?", self.program, self.trace_file));
+        }
+        let result = stdout.lines().next().unwrap_or_default().trim().to_string();
+        log::info!("{} returned {}", self.program, if result.is_empty() { "nothing" } else { &result });
+        Ok(SolverRun { result, stdout, stderr, exit_code: status.code(), trace_file: self.trace_file.clone() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+    use crate::parsers::{test_logs, Log, Z3LogParser};
+    use crate::parsers::z3parser1::Z3Parser1;
+
+    /// Writes a shell script standing in for Z3, which writes `SIMPLE` to the trace file it is given,
+    /// and returns a runner that runs it.
+    #[cfg(unix)]
+    fn fake_solver(name: &str) -> SolverRunner {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("fake_z3_{}_{}", name, std::process::id()));
+        let script = format!("#!/bin/sh
+for arg in \"$@\"; do
+    case \"$arg\" in
+        trace_file_name=*) trace=\"${{arg#trace_file_name=}}\" ;;
+    esac
+done
+echo \"$@\" >&2
+cat > \"$trace\" <<'EOF'
+{}EOF
+echo unsat
+", test_logs::SIMPLE);
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        // in a directory that does not exist yet, like `out/` before the first run
+        let trace_file = dir.join(format!("fake_z3_{}_{}_out", name, std::process::id())).join("trace.log");
+        SolverRunner {
+            program: path.to_str().unwrap().to_string(),
+            args: vec![String::from("-v:0")],
+            trace_file: trace_file.to_str().unwrap().to_string(),
+            timeout: Some(Duration::from_secs(10)),
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_solver() {
+        let runner = fake_solver("run");
+        let script = runner.program.clone();
+        let run = runner.run("input.smt2").unwrap();
+        assert_eq!(run.result, "unsat");
+        assert_eq!(run.exit_code, Some(0));
+        assert_eq!(run.stderr.trim(), format!("-v:0 trace=true proof=true trace_file_name={} input.smt2", runner.trace_file));
+        let mut parser = Z3Parser1::default();
+        parser.main_parse_loop(Log::Filename(run.trace_file)).unwrap();
+        assert_eq!(parser.instantiations().keys().copied().collect::<Vec<_>>(), vec![10, 17]);
+
+        let runner = SolverRunner { program: String::from("true"), ..runner };
+        assert!(runner.run("input.smt2").unwrap_err().contains("wrote no trace"));
+        let runner = SolverRunner { program: String::from("/nonexistent/z3"), ..runner };
+        assert!(runner.run("input.smt2").unwrap_err().starts_with("Failed to run the solver"));
+        fs::remove_dir_all(Path::new(&runner.trace_file).parent().unwrap()).unwrap();
+        fs::remove_file(script).unwrap();
+    }
+
+    #[test]
+    fn test_is_smt2_file() {
+        assert!(is_smt2_file("tests/input.smt2") && !is_smt2_file("logs/z3.log"));
+    }
+}
@@ -0,0 +1,240 @@
+use rusqlite::{params, Connection, Transaction};
+use serde::Serialize;
+
+use crate::binary_export::ExportDataRef;
+use crate::items::BlamedTermItem;
+
+/// Table definitions. Terms and quantifiers are identified by their full ID (e.g. `#12`, `datatype#3`),
+/// instantiations by their line number.
+const SCHEMA: &str = "
+CREATE TABLE terms (
+    id TEXT PRIMARY KEY,
+    namespace TEXT NOT NULL,
+    num INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    name TEXT NOT NULL,
+    theory TEXT NOT NULL,
+    text TEXT NOT NULL,
+    resp_inst_line_no INTEGER
+);
+CREATE TABLE term_children (
+    term_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    child_id TEXT NOT NULL
+);
+CREATE TABLE quantifiers (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    term TEXT NOT NULL,
+    num_vars INTEGER NOT NULL,
+    cost REAL NOT NULL,
+    num_instances INTEGER NOT NULL
+);
+CREATE TABLE quantifier_vars (
+    quant_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    sort TEXT NOT NULL
+);
+CREATE TABLE instantiations (
+    line_no INTEGER PRIMARY KEY,
+    match_line_no INTEGER NOT NULL,
+    fingerprint INTEGER NOT NULL,
+    resulting_term TEXT NOT NULL,
+    z3_gen INTEGER NOT NULL,
+    cost REAL NOT NULL,
+    quant_id TEXT NOT NULL,
+    pattern_id TEXT NOT NULL
+);
+CREATE TABLE bound_terms (
+    inst_line_no INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    term_id TEXT NOT NULL
+);
+CREATE TABLE blamed_terms (
+    inst_line_no INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    term_id TEXT NOT NULL,
+    equal_term_id TEXT
+);
+CREATE TABLE yielded_terms (
+    inst_line_no INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    term_id TEXT NOT NULL
+);
+CREATE TABLE inst_equality_expls (
+    inst_line_no INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    eq_expl_id TEXT NOT NULL
+);
+CREATE TABLE eq_expls (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    to_term TEXT,
+    details TEXT NOT NULL
+);
+CREATE TABLE dependencies (
+    from_line_no INTEGER NOT NULL,
+    to_line_no INTEGER NOT NULL,
+    blamed TEXT NOT NULL,
+    dep_type TEXT NOT NULL,
+    quant_id TEXT NOT NULL
+);
+CREATE INDEX term_children_term ON term_children (term_id);
+CREATE INDEX quantifier_vars_quant ON quantifier_vars (quant_id);
+CREATE INDEX instantiations_quant ON instantiations (quant_id);
+CREATE INDEX bound_terms_inst ON bound_terms (inst_line_no);
+CREATE INDEX bound_terms_term ON bound_terms (term_id);
+CREATE INDEX blamed_terms_inst ON blamed_terms (inst_line_no);
+CREATE INDEX blamed_terms_term ON blamed_terms (term_id);
+CREATE INDEX yielded_terms_inst ON yielded_terms (inst_line_no);
+CREATE INDEX yielded_terms_term ON yielded_terms (term_id);
+CREATE INDEX inst_equality_expls_inst ON inst_equality_expls (inst_line_no);
+CREATE INDEX dependencies_from ON dependencies (from_line_no);
+CREATE INDEX dependencies_to ON dependencies (to_line_no);
+CREATE INDEX dependencies_quant ON dependencies (quant_id);
+";
+
+/// Writes parsed results into normalized tables of a new SQLite database `filename` (replacing any existing file).
+pub fn save_sqlite(filename: &str, data: &ExportDataRef) -> Result<(), String> {
+    if std::path::Path::new(filename).exists() {
+        std::fs::remove_file(filename).map_err(|e| format!("Error removing {}: {}", filename, e))?;
+    }
+    let mut conn = Connection::open(filename).map_err(|e| e.to_string())?;
+    write_tables(&mut conn, data).map_err(|e| e.to_string())
+}
+
+/// Creates all tables in `conn` and fills them with `data` in a single transaction.
+pub fn write_tables(conn: &mut Connection, data: &ExportDataRef) -> rusqlite::Result<()> {
+    conn.execute_batch(SCHEMA)?;
+    let tx = conn.transaction()?;
+    insert_terms(&tx, data)?;
+    insert_quantifiers(&tx, data)?;
+    insert_instantiations(&tx, data)?;
+    insert_eq_expls(&tx, data)?;
+    insert_dependencies(&tx, data)?;
+    tx.commit()
+}
+
+/// Full ID of a term or quantifier from its namespace and number.
+fn full_id(namespace: &str, num: usize) -> String {
+    format!("{}#{}", namespace, num)
+}
+
+/// Name of an enum variant (or the value of a plain string), taken from its `Serialize` implementation.
+fn variant_name<T: Serialize>(item: &T) -> String {
+    match serde_json::to_value(item) {
+        Ok(serde_json::Value::String(name)) => name,
+        Ok(serde_json::Value::Object(map)) => map.keys().next().cloned().unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+fn insert_terms(tx: &Transaction, data: &ExportDataRef) -> rusqlite::Result<()> {
+    let mut term_stmt = tx.prepare("INSERT INTO terms VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")?;
+    let mut child_stmt = tx.prepare("INSERT INTO term_children VALUES (?1, ?2, ?3)")?;
+    for (namespace, ns_map) in &data.terms.0 {
+        for (num, term) in ns_map {
+            let id = full_id(namespace, *num);
+            term_stmt.execute(params![id, namespace, num, term.kind, term.name, term.theory, term.text, term.resp_inst_line_no])?;
+            for (i, child) in term.child_ids.iter().enumerate() {
+                child_stmt.execute(params![id, i, child])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn insert_quantifiers(tx: &Transaction, data: &ExportDataRef) -> rusqlite::Result<()> {
+    let mut quant_stmt = tx.prepare("INSERT INTO quantifiers VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+    let mut var_stmt = tx.prepare("INSERT INTO quantifier_vars VALUES (?1, ?2, ?3, ?4)")?;
+    for (namespace, ns_map) in &data.quantifiers.0 {
+        for (num, quant) in ns_map {
+            let id = full_id(namespace, *num);
+            quant_stmt.execute(params![id, quant.name, quant.term, quant.num_vars, quant.cost, quant.instances.len()])?;
+            for (i, (name, sort)) in quant.vars.iter().enumerate() {
+                var_stmt.execute(params![id, i, name, sort])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn insert_instantiations(tx: &Transaction, data: &ExportDataRef) -> rusqlite::Result<()> {
+    let mut inst_stmt = tx.prepare("INSERT INTO instantiations VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")?;
+    let mut bound_stmt = tx.prepare("INSERT INTO bound_terms VALUES (?1, ?2, ?3)")?;
+    let mut blamed_stmt = tx.prepare("INSERT INTO blamed_terms VALUES (?1, ?2, ?3, ?4)")?;
+    let mut yielded_stmt = tx.prepare("INSERT INTO yielded_terms VALUES (?1, ?2, ?3)")?;
+    let mut eq_stmt = tx.prepare("INSERT INTO inst_equality_expls VALUES (?1, ?2, ?3)")?;
+    for inst in data.instantiations.values() {
+        // SQLite integers are signed; fingerprints are stored with the same bits
+        inst_stmt.execute(params![inst.line_no, inst.match_line_no, inst.fingerprint as i64, inst.resulting_term,
+            inst.z3_gen, inst.cost, inst.quant_id, inst.pattern_id])?;
+        for (i, term) in inst.bound_terms.iter().enumerate() {
+            bound_stmt.execute(params![inst.line_no, i, term])?;
+        }
+        for (i, blamed) in inst.blamed_terms.iter().enumerate() {
+            match blamed {
+                BlamedTermItem::Single(term) => blamed_stmt.execute(params![inst.line_no, i, term, None::<String>])?,
+                BlamedTermItem::Pair(term, equal_term) => blamed_stmt.execute(params![inst.line_no, i, term, equal_term])?,
+            };
+        }
+        for (i, term) in inst.yields_terms.iter().enumerate() {
+            yielded_stmt.execute(params![inst.line_no, i, term])?;
+        }
+        for (i, eq) in inst.equality_expls.iter().enumerate() {
+            eq_stmt.execute(params![inst.line_no, i, eq])?;
+        }
+    }
+    Ok(())
+}
+
+fn insert_eq_expls(tx: &Transaction, data: &ExportDataRef) -> rusqlite::Result<()> {
+    use crate::items::EqualityExpl::*;
+    let mut stmt = tx.prepare("INSERT INTO eq_expls VALUES (?1, ?2, ?3, ?4)")?;
+    for (id, eq) in data.eq_expls {
+        let to = match eq {
+            Root { .. } => None,
+            Literal { to, .. } | Congruence { to, .. } | Theory { to, .. } | Axiom { to, .. } | Unknown { to, .. } => Some(to),
+        };
+        let details = serde_json::to_string(eq).map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+        stmt.execute(params![id, variant_name(eq), to, details])?;
+    }
+    Ok(())
+}
+
+fn insert_dependencies(tx: &Transaction, data: &ExportDataRef) -> rusqlite::Result<()> {
+    let mut stmt = tx.prepare("INSERT INTO dependencies VALUES (?1, ?2, ?3, ?4, ?5)")?;
+    for dep in data.dependencies {
+        stmt.execute(params![dep.from, dep.to, dep.blamed, variant_name(&dep.dep_type), dep.quant])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::test_logs::{parse, simple_with_eq_expls};
+
+    #[test]
+    fn test_variant_name() {
+        use crate::items::{DepType, EqualityExpl};
+        assert_eq!(variant_name(&DepType::Equality), "Equality");
+        assert_eq!(variant_name(&EqualityExpl::Root { id: String::from("#1") }), "Root");
+        assert_eq!(variant_name(&BlamedTermItem::Pair(String::from("#1"), String::from("#2"))), "Pair");
+    }
+
+    #[test]
+    fn test_sqlite_export() {
+        let parser = parse(simple_with_eq_expls());
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        write_tables(&mut conn, &parser.export_data()).unwrap();
+        let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM instantiations WHERE quant_id = '#5'"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM terms"), 9);
+        assert_eq!(count("SELECT COUNT(*) FROM dependencies WHERE from_line_no = 10 AND to_line_no = 17 AND dep_type = 'Term'"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM yielded_terms WHERE inst_line_no = 10"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM blamed_terms b JOIN yielded_terms y ON b.term_id = y.term_id"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM eq_expls WHERE kind = 'Literal' AND to_term = '#8'"), 1);
+    }
+}
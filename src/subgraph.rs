@@ -0,0 +1,131 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque};
+use serde::Serialize;
+
+use crate::file_io::SubgraphDirection;
+use crate::items::Dependency;
+
+/// An instantiation in a subgraph.
+#[derive(Debug, Serialize)]
+pub struct SubgraphNode {
+    /// number of dependency edges between this instantiation and the center
+    pub distance: usize,
+    /// ID of the instantiated quantifier
+    pub quant: String,
+}
+
+/// The instantiations that led to a chosen instantiation (its ancestors) and/or that it caused (its descendants).
+#[derive(Debug, Serialize)]
+pub struct Subgraph {
+    /// line number of the chosen instantiation
+    pub center: usize,
+    /// instantiations in the subgraph by line number, including the center
+    pub nodes: BTreeMap<usize, SubgraphNode>,
+    /// dependencies between instantiations in the subgraph
+    pub dependencies: Vec<Dependency>,
+}
+
+impl Subgraph {
+    /// Collects the instantiations at most `max_depth` dependency edges (any number if `None`) before and/or after
+    /// the instantiation at line `center`, following the dependencies in `direction`.
+    /// # Errors
+    /// Errors if there is no instantiation at line `center`.
+    pub fn extract(dependencies: &[Dependency], center: usize, direction: &SubgraphDirection, max_depth: Option<usize>) -> Result<Subgraph, String> {
+        let mut quants = HashMap::new();
+        let mut parents: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        for dep in dependencies {
+            quants.entry(dep.to).or_insert(&dep.quant);
+            if dep.from != 0 {
+                parents.entry(dep.to).or_default().push(dep.from);
+                children.entry(dep.from).or_default().push(dep.to);
+            }
+        }
+        if !quants.contains_key(&center) {
+            return Err(format!("No instantiation at line {}", center));
+        }
+        let mut distances = BTreeMap::from([(center, 0)]);
+        if *direction != SubgraphDirection::Descendants {
+            search(&parents, center, max_depth, &mut distances);
+        }
+        if *direction != SubgraphDirection::Ancestors {
+            search(&children, center, max_depth, &mut distances);
+        }
+        let nodes = distances.into_iter()
+            .map(|(line_no, distance)| {
+                let quant = quants.get(&line_no).map_or_else(String::new, |quant| quant.to_string());
+                (line_no, SubgraphNode { distance, quant })
+            })
+            .collect::<BTreeMap<_, _>>();
+        let dependencies = dependencies.iter()
+            .filter(|dep| nodes.contains_key(&dep.from) && nodes.contains_key(&dep.to))
+            .cloned()
+            .collect();
+        Ok(Subgraph { center, nodes, dependencies })
+    }
+}
+
+/// Breadth-first search from `start` along `edges`, recording the distance of each instantiation reached
+/// within `max_depth` steps in `distances` (keeping smaller distances already recorded).
+fn search(edges: &HashMap<usize, Vec<usize>>, start: usize, max_depth: Option<usize>, distances: &mut BTreeMap<usize, usize>) {
+    let mut visited = HashMap::from([(start, 0)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(line_no) = queue.pop_front() {
+        let distance = visited[&line_no];
+        if max_depth.is_some_and(|max| distance >= max) {
+            continue;
+        }
+        for &next in edges.get(&line_no).into_iter().flatten() {
+            if let Entry::Vacant(entry) = visited.entry(next) {
+                entry.insert(distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    for (line_no, distance) in visited {
+        let entry = distances.entry(line_no).or_insert(distance);
+        *entry = (*entry).min(distance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_io::NodeSize;
+    use crate::parsers::{test_logs::{self, parse}, Log};
+    use crate::items::DepType;
+
+    /// 1 -> 2 -> 3 -> 4, 5 -> 3
+    fn chain() -> Vec<Dependency> {
+        let dep = |from, to| Dependency { from, to, blamed: String::new(), dep_type: DepType::Term, quant: format!("#{}", to) };
+        vec![dep(0, 1), dep(1, 2), dep(2, 3), dep(5, 3), dep(3, 4), dep(0, 5)]
+    }
+
+    #[test]
+    fn test_extract() {
+        let deps = chain();
+        let lines = |sub: &Subgraph| sub.nodes.keys().copied().collect::<Vec<_>>();
+        let sub = Subgraph::extract(&deps, 3, &SubgraphDirection::Ancestors, None).unwrap();
+        assert_eq!(lines(&sub), vec![1, 2, 3, 5]);
+        assert_eq!(sub.nodes[&1].distance, 2);
+        assert_eq!(sub.nodes[&1].quant, "#1");
+        assert_eq!(sub.dependencies.len(), 3);
+        let sub = Subgraph::extract(&deps, 2, &SubgraphDirection::Descendants, Some(1)).unwrap();
+        assert_eq!(lines(&sub), vec![2, 3]);
+        // siblings (5 shares a child with 2) are not included
+        let sub = Subgraph::extract(&deps, 2, &SubgraphDirection::Both, None).unwrap();
+        assert_eq!(lines(&sub), vec![1, 2, 3, 4]);
+        assert!(Subgraph::extract(&deps, 6, &SubgraphDirection::Both, None).is_err());
+    }
+
+    #[test]
+    fn test_subgraph_dot() {
+        let mut parser = parse(Log::File(test_logs::SIMPLE.to_string()));
+        parser.update_costs();
+        let subgraph = parser.subgraph(17, &SubgraphDirection::Ancestors, Some(1)).unwrap();
+        let dot = parser.get_subgraph_dot(&subgraph, &NodeSize::Fixed);
+        assert!(dot.starts_with("digraph subgraph {\n\t10 [ "));
+        assert!(dot.contains("\t17 [ class=\"quant_5\", tooltip=\"quant_#5\", id=\"inst-17\", URL=\"#inst-17\", label=\"@17\\nq1\\ncost 1.0\", penwidth=3 ]"));
+        assert!(dot.contains("\t10 -> 17 [ style=solid"));
+        assert!(parser.subgraph(11, &SubgraphDirection::Both, None).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use crate::render::native::escape_xml;
+
+/// Attributes to add to graph nodes, keyed by node ID (the `<title>` of the node in the SVG).
+/// Attribute names are given without the `data-` prefix.
+pub type NodeData = BTreeMap<String, Vec<(String, String)>>;
+
+/// How a style sheet is attached to an SVG.
+pub enum Stylesheet<'a> {
+    /// Reference to an external style sheet at the given URL.
+    Link(&'a str),
+    /// CSS rules embedded in the SVG.
+    Embed(&'a str),
+}
+
+/// Byte position just after the start tag of the root `<svg>` element in `svg`,
+/// skipping the XML declaration, comments, processing instructions and doctype before it.
+/// # Errors
+/// Errors if `svg` does not start with an `<svg>` element.
+pub fn find_root_content_start(svg: &str) -> Result<usize, String> {
+    let mut pos = 0;
+    loop {
+        let start = pos + svg[pos..].find('<').ok_or("No <svg> element found")?;
+        let rest = &svg[start..];
+        let skip_to = if rest.starts_with("<?") {
+            rest.find("?>").map(|end| end + 2)
+        } else if rest.starts_with("<!--") {
+            rest.find("-->").map(|end| end + 3)
+        } else if rest.starts_with("<!") {
+            find_tag_end(rest)
+        } else if is_start_tag(rest, "svg") {
+            let end = find_tag_end(rest).ok_or("Unterminated <svg> start tag")?;
+            if rest[..end].ends_with("/>") {
+                return Err(String::from("The <svg> element is empty"));
+            }
+            return Ok(start + end);
+        } else {
+            return Err(format!("Expected <svg> element, found {}", rest.split_whitespace().next().unwrap_or_default()));
+        };
+        pos = start + skip_to.ok_or("Unterminated markup before <svg> element")?;
+    }
+}
+
+/// Returns `svg` with the style sheet inserted as the first child of the root `<svg>` element.
+/// # Errors
+/// Errors if the root `<svg>` element could not be found.
+pub fn add_stylesheet(svg: &str, stylesheet: &Stylesheet) -> Result<String, String> {
+    let insert_at = find_root_content_start(svg)?;
+    let element = match stylesheet {
+        Stylesheet::Link(href) => format!(
+            "\n<link xmlns=\"http://www.w3.org/1999/xhtml\" rel=\"stylesheet\" href=\"{}\" type=\"text/css\" />",
+            escape_xml(href)
+        ),
+        // "]]>" would end the CDATA section, so it is split across two sections
+        Stylesheet::Embed(css) => format!(
+            "\n<style type=\"text/css\"><![CDATA[\n{}]]></style>",
+            css.replace("]]>", "]]]]><![CDATA[>")
+        ),
+    };
+    Ok(format!("{}{}{}", &svg[..insert_at], element, &svg[insert_at..]))
+}
+
+/// Returns `svg` with `data-*` attributes from `data` added to the `<g class="node">` element of each node.
+/// Nodes are identified by their `<title>`, which Graphviz and the native renderer set to the Dot node ID.
+pub fn add_node_data(svg: &str, data: &NodeData) -> String {
+    let mut result = String::with_capacity(svg.len());
+    let mut pos = 0;
+    while let Some(offset) = svg[pos..].find("<g") {
+        let start = pos + offset;
+        let rest = &svg[start..];
+        let Some(end) = find_tag_end(rest).filter(|_| is_start_tag(rest, "g")) else {
+            result += &svg[pos..start + 2];
+            pos = start + 2;
+            continue;
+        };
+        let tag = &rest[..end];
+        let attrs = attr_value(tag, "class")
+            .filter(|class| class.split_whitespace().any(|c| c == "node"))
+            .and_then(|_| node_title(&rest[end..]))
+            .and_then(|title| data.get(title.trim()));
+        result += &svg[pos..start];
+        match attrs {
+            Some(attrs) => {
+                // insert before the closing ">" (or "/>")
+                let close = if tag.ends_with("/>") { end - 2 } else { end - 1 };
+                result += &tag[..close];
+                for (name, value) in attrs {
+                    result += &format!(" data-{}=\"{}\"", name, escape_xml(value));
+                }
+                result += &tag[close..];
+            },
+            None => result += tag,
+        }
+        pos = start + end;
+    }
+    result += &svg[pos..];
+    result
+}
+
+/// True if `s` starts with a start tag of element `name`.
+fn is_start_tag(s: &str, name: &str) -> bool {
+    s.strip_prefix('<')
+        .and_then(|s| s.strip_prefix(name))
+        .and_then(|s| s.chars().next())
+        .is_some_and(|c| c.is_whitespace() || c == '>' || c == '/')
+}
+
+/// Length of the tag at the start of `s` (up to and including its closing `>`), ignoring `>` in quoted attribute values.
+fn find_tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Value of attribute `name` in start tag `tag`.
+fn attr_value<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(i) = rest.find(name) {
+        let preceded_by_space = rest[..i].ends_with(char::is_whitespace);
+        rest = &rest[i + name.len()..];
+        if preceded_by_space {
+            if let Some(value) = rest.trim_start().strip_prefix('=') {
+                let value = value.trim_start();
+                let quote = value.chars().next()?;
+                let value = &value[1..];
+                return value.find(quote).map(|end| &value[..end]);
+            }
+        }
+    }
+    None
+}
+
+/// Text of the `<title>` element at the start of the contents of a group `content`, if there is one.
+fn node_title(content: &str) -> Option<&str> {
+    let start = content.find("<title>")?;
+    if content[..start].contains('<') {
+        return None;
+    }
+    let content = &content[start + "<title>".len()..];
+    content.find("</title>").map(|end| &content[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_io::Settings;
+    use crate::parsers::{test_logs::{self, parse}, Log, Z3LogParser};
+
+    /// Graphviz output, with a comment where older versions put the stylesheet insertion point
+    const GRAPHVIZ_SVG: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN"
+ "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
+<!-- Generated by graphviz version 9.0.0 (0)
+ -->
+<!-- Title: instantiations Pages: 1 -->
+<svg width="62pt" height="116pt"
+ viewBox="0.00 0.00 62.00 116.00" xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+<g id="graph0" class="graph" transform="scale(1 1) rotate(0) translate(4 112)">
+<title>instantiations</title>
+<!-- 10 -->
+<g id="node1" class="node quant_5">
+<title>10</title>
+<g id="a_node1"><a xlink:title="quant_#5">
+<ellipse fill="none" stroke="black" cx="27" cy="-90" rx="27" ry="18"/>
+</a></g>
+</g>
+<!-- 10&#45;&gt;17 -->
+<g id="edge1" class="edge">
+<title>10&#45;&gt;17</title>
+</g>
+</g>
+</svg>
+"#;
+
+    #[test]
+    fn test_find_root() {
+        let start = find_root_content_start(GRAPHVIZ_SVG).unwrap();
+        assert!(GRAPHVIZ_SVG[..start].ends_with("xmlns:xlink=\"http://www.w3.org/1999/xlink\">"));
+        assert_eq!(find_root_content_start("<svg a=\">\"><g/></svg>"), Ok(11));
+        assert!(find_root_content_start("<html><svg></svg></html>").is_err());
+        assert!(find_root_content_start("<!-- <svg> -->").is_err());
+    }
+
+    #[test]
+    fn test_add_stylesheet() {
+        let svg = add_stylesheet(GRAPHVIZ_SVG, &Stylesheet::Link("styles.css")).unwrap();
+        assert!(svg.contains("xlink\">\n<link xmlns=\"http://www.w3.org/1999/xhtml\" rel=\"stylesheet\" href=\"styles.css\" type=\"text/css\" />\n<g id=\"graph0\""));
+        let svg = add_stylesheet("<svg>\n</svg>", &Stylesheet::Embed(".a { }\n")).unwrap();
+        assert_eq!(svg, "<svg>\n<style type=\"text/css\"><![CDATA[\n.a { }\n]]></style>\n</svg>");
+    }
+
+    #[test]
+    fn test_add_node_data() {
+        let data = NodeData::from([(String::from("10"), vec![
+            (String::from("inst-line"), String::from("10")),
+            (String::from("quantifier"), String::from("a<b")),
+        ])]);
+        let svg = add_node_data(GRAPHVIZ_SVG, &data);
+        assert!(svg.contains("<g id=\"node1\" class=\"node quant_5\" data-inst-line=\"10\" data-quantifier=\"a&lt;b\">\n<title>10</title>"));
+        // nested groups and edges are unchanged
+        assert!(svg.contains("<g id=\"a_node1\"><a"));
+        assert!(svg.contains("<g id=\"edge1\" class=\"edge\">"));
+        assert_eq!(svg.len(), GRAPHVIZ_SVG.len() + " data-inst-line=\"10\" data-quantifier=\"a&lt;b\"".len());
+    }
+
+    #[test]
+    fn test_svg_node_data() {
+        let parser = parse(Log::File(test_logs::SIMPLE.to_string()));
+        let svg = crate::render::native::render_dot(&parser.get_dot_output_as_string(&Settings::default()));
+        let svg = add_node_data(&svg, &parser.svg_node_data());
+        assert!(svg.contains("data-inst-line=\"17\" data-quant-id=\"#5\" data-quantifier=\"q1\" data-cost=\"1\""));
+    }
+}